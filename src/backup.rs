@@ -0,0 +1,156 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{find_index, path_to_str, IndexFormat, Scope};
+use crate::list::format_time;
+
+/// Directory in `.cmd` with the backups, each of them named by the unix time of its creation.
+const BACKUPS: &str = "backups";
+
+fn backups_dir(scope: &Scope) -> PathBuf {
+    scope.path.join(".cmd").join(BACKUPS)
+}
+
+fn is_index(name: &str) -> bool {
+    IndexFormat::ALL.iter().any(|f| f.file_name() == name)
+}
+
+/// Entries of `.cmd` which a full backup leaves out, i.e. the backups and unfinished writes.
+fn is_excluded(name: &str) -> bool {
+    name == BACKUPS || name.ends_with(".tmp")
+}
+
+/// Names of the backups of the scope, oldest first.
+fn backups(scope: &Scope) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(backups_dir(scope)) else {
+        return vec![];
+    };
+    let mut names: Vec<String> = entries.flatten()
+        .filter(|e| e.path().is_dir())
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort_by_cached_key(|name| (created_at(name), name.to_owned()));
+    names
+}
+
+fn created_at(name: &str) -> u64 {
+    name.split('-').next().and_then(|secs| secs.parse().ok()).unwrap_or(0)
+}
+
+/// A full backup contains also the scripts, not just the index.
+fn is_full(dir: &Path) -> bool {
+    std::fs::read_dir(dir).map(|entries| entries.flatten().any(|e| !is_index(&e.file_name().to_string_lossy()))).unwrap_or(false)
+}
+
+/// Makes `to` a copy of `from`, top-level entries for which `skip` holds are ignored on both sides.
+pub fn mirror(from: &Path, to: &Path, skip: &dyn Fn(&str) -> bool) -> Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(to)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if skip(&name.to_string_lossy()) || from.join(&name).exists() {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            std::fs::remove_dir_all(entry.path())?;
+        } else {
+            std::fs::remove_file(entry.path())?;
+        }
+    }
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if skip(&name.to_string_lossy()) {
+            continue;
+        }
+        let (source, target) = (entry.path(), to.join(&name));
+        if source.is_dir() {
+            if target.exists() && !target.is_dir() {
+                std::fs::remove_file(&target)?;
+            }
+            mirror(&source, &target, &|_| false)?;
+        } else {
+            if target.is_dir() {
+                std::fs::remove_dir_all(&target)?;
+            }
+            std::fs::copy(&source, &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Copies the index, or the whole `.cmd` with `full`, into a new backup and returns its name.
+fn create(scope: &Scope, full: bool) -> Result<String> {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let mut name = secs.to_string();
+    for n in 2.. {
+        if !backups_dir(scope).join(&name).exists() {
+            break;
+        }
+        name = format!("{secs}-{n}");
+    }
+    let dir = backups_dir(scope).join(&name);
+    let skip: &dyn Fn(&str) -> bool = if full { &is_excluded } else { &|name| !is_index(name) };
+    if let Err(e) = mirror(&scope.path.join(".cmd"), &dir, skip) {
+        let _ = std::fs::remove_dir_all(&dir);
+        return Err(e);
+    }
+    Ok(name)
+}
+
+/// Replaces the index, or the whole `.cmd` if the backup is full, by the backup and returns
+/// the name of the backup of the replaced state.
+fn restore(scope: &Scope, name: &str) -> Result<String> {
+    if !backups(scope).iter().any(|n| n == name) {
+        anyhow::bail!("there is no backup '{name}' of the {} scope, see `backup --list`", scope.kind.name());
+    }
+    let dir = backups_dir(scope).join(name);
+    let full = is_full(&dir);
+    let previous = create(scope, full)?;
+    let skip: &dyn Fn(&str) -> bool = if full { &is_excluded } else { &|name| !is_index(name) };
+    mirror(&dir, &scope.path.join(".cmd"), skip)?;
+    Ok(previous)
+}
+
+pub fn cmd_backup(scope: &Scope, full: bool) {
+    match find_index(&scope.path) {
+        Ok(Some(_)) => {},
+        Ok(None) => {
+            println!("ERR: the {} scope has no index to back up", scope.kind.name());
+            std::process::exit(1);
+        },
+        Err(e) => panic!("{e}"),
+    }
+    match create(scope, full) {
+        Ok(name) => println!("INFO: backup {name} of the {} scope created in {}", scope.kind.name(), path_to_str(&backups_dir(scope).join(&name))),
+        Err(e) => {
+            println!("ERR: unable to back up the {} scope: {e}", scope.kind.name());
+            std::process::exit(1);
+        },
+    }
+}
+
+pub fn cmd_list_backups(scope: &Scope) {
+    let names = backups(scope);
+    if names.is_empty() {
+        println!("INFO: the {} scope has no backups", scope.kind.name());
+        return;
+    }
+    let width = names.iter().map(|n| n.len()).max().unwrap_or(0);
+    for name in names {
+        let created = format_time(UNIX_EPOCH + Duration::from_secs(created_at(&name)));
+        let kind = if is_full(&backups_dir(scope).join(&name)) { "full" } else { "index" };
+        println!("{name:width$}  {created}  {kind}");
+    }
+}
+
+pub fn cmd_restore(scope: &Scope, name: &str) {
+    match restore(scope, name) {
+        Ok(previous) => println!("INFO: the {} scope was restored from backup {name}, its previous state is kept as backup {previous}", scope.kind.name()),
+        Err(e) => {
+            println!("ERR: unable to restore the {} scope: {e}", scope.kind.name());
+            std::process::exit(1);
+        },
+    }
+}
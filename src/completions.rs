@@ -0,0 +1,449 @@
+use clap::Command;
+use clap_complete::Shell;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub fn print_completions(shell: Shell, dynamic: bool, bin_name: &str, builder: &mut Command) {
+    write_completions(shell, dynamic, bin_name, builder, &mut std::io::stdout());
+}
+
+/// The values accepted as the shell, they are the ones `clap_complete` generates for.
+pub fn print_shells() {
+    for shell in <Shell as clap::ValueEnum>::value_variants() {
+        if let Some(value) = clap::ValueEnum::to_possible_value(shell) {
+            println!("{}", value.get_name());
+        }
+    }
+}
+
+/// Shells with a `--dynamic` variant of the completions.
+pub const DYNAMIC_SHELLS: [Shell; 3] = [Shell::Bash, Shell::Zsh, Shell::Fish];
+
+fn write_completions(shell: Shell, dynamic: bool, bin_name: &str, builder: &mut Command, out: &mut dyn Write) {
+    if !dynamic {
+        match shell {
+            Shell::Zsh | Shell::Fish => {
+                clap_complete::generate(shell, &mut without_dashed_aliases(builder), bin_name, out);
+            },
+            _ => clap_complete::generate(shell, builder, bin_name, out),
+        }
+        return;
+    }
+    match shell {
+        Shell::Bash => write!(out, "{}", dynamic_bash(bin_name, &builtin_words(&crate::build_cli()))).expect("unable to write completions"),
+        Shell::Zsh => write!(out, "{}", dynamic_zsh(bin_name)).expect("unable to write completions"),
+        Shell::Fish => write!(out, "{}", dynamic_fish(bin_name)).expect("unable to write completions"),
+        _ => unreachable!("dynamic completions are not supported for {shell}"),
+    }
+}
+
+fn data_home() -> Option<PathBuf> {
+    match std::env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")),
+    }
+}
+
+pub fn config_home() -> Option<PathBuf> {
+    match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")),
+    }
+}
+
+/// Conventional per-user location of the completion script, none for shells without one.
+pub fn install_path(shell: Shell, bin_name: &str) -> Option<PathBuf> {
+    match shell {
+        Shell::Bash => data_home().map(|dir| dir.join("bash-completion/completions").join(bin_name)),
+        Shell::Zsh => std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".zfunc").join(format!("_{bin_name}"))),
+        Shell::Fish => config_home().map(|dir| dir.join("fish/completions").join(format!("{bin_name}.fish"))),
+        _ => None,
+    }
+}
+
+/// Remembers which completions were installed, so that their users can be told when they get stale.
+fn marker_path(bin_name: &str) -> Option<PathBuf> {
+    data_home().map(|dir| dir.join(bin_name).join("installed-completions"))
+}
+
+/// Record of an installed completion script.
+pub struct Installed {
+    pub shell: Shell,
+    pub path: PathBuf,
+    pub dynamic: bool,
+    /// The script is a block between markers within a file of the user.
+    pub merged: bool,
+}
+
+/// Completions installed so far, one per shell.
+pub fn installed(bin_name: &str) -> Vec<Installed> {
+    let Some(data) = marker_path(bin_name).and_then(|path| std::fs::read_to_string(path).ok()) else {
+        return vec![];
+    };
+    data.lines().filter_map(|line| {
+        let mut fields = line.split('\t');
+        let shell = fields.next()?.parse::<Shell>().ok()?;
+        let path = PathBuf::from(fields.next()?);
+        let dynamic = fields.next() == Some("dynamic");
+        let merged = fields.next() == Some("merged");
+        Some(Installed{shell, path, dynamic, merged})
+    }).collect()
+}
+
+/// Writes the completion script to its conventional location, or into the block of the `merge` file,
+/// and records it in the marker file.
+pub fn install(shell: Shell, dynamic: bool, merge: Option<&Path>, bin_name: &str, builder: &mut Command) -> std::io::Result<PathBuf> {
+    let path = match merge {
+        Some(path) => path.to_path_buf(),
+        None => install_path(shell, bin_name).ok_or_else(|| {
+            std::io::Error::other(format!("there is no known install location of completions for {shell}"))
+        })?,
+    };
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut data = vec![];
+    write_completions(shell, dynamic, bin_name, builder, &mut data);
+    let script = String::from_utf8(data).expect("generated completions are valid utf-8");
+    if merge.is_some() {
+        let existing = match std::fs::read_to_string(&path) {
+            Ok(existing) => existing,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        std::fs::write(&path, merge_block(&existing, &script, bin_name))?;
+    } else {
+        std::fs::write(&path, script)?;
+    }
+    let mut entries: Vec<Installed> = installed(bin_name).into_iter().filter(|i| i.shell != shell).collect();
+    entries.push(Installed{shell, path: path.to_owned(), dynamic, merged: merge.is_some()});
+    save_installed(bin_name, &entries)?;
+    Ok(path)
+}
+
+/// Removes the installed completion file or its block, returns where it was or none if nothing was found.
+pub fn uninstall(shell: Shell, bin_name: &str) -> std::io::Result<Option<PathBuf>> {
+    let (entries, others): (Vec<Installed>, Vec<Installed>) = installed(bin_name).into_iter().partition(|i| i.shell == shell);
+    let removed = match entries.first() {
+        Some(entry) if entry.merged => {
+            let existing = std::fs::read_to_string(&entry.path).unwrap_or_default();
+            match strip_block(&existing, bin_name) {
+                Some(stripped) => {
+                    std::fs::write(&entry.path, stripped)?;
+                    Some(entry.path.to_owned())
+                },
+                None => None,
+            }
+        },
+        Some(entry) => remove_file(&entry.path)?,
+        None => match install_path(shell, bin_name) {
+            Some(path) => remove_file(&path)?,
+            None => None,
+        },
+    };
+    if !entries.is_empty() {
+        save_installed(bin_name, &others)?;
+    }
+    Ok(removed)
+}
+
+fn remove_file(path: &Path) -> std::io::Result<Option<PathBuf>> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(Some(path.to_path_buf())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn save_installed(bin_name: &str, entries: &[Installed]) -> std::io::Result<()> {
+    let marker = marker_path(bin_name).ok_or_else(|| std::io::Error::other("neither XDG_DATA_HOME nor HOME is set"))?;
+    let lines: Vec<String> = entries.iter().map(|i| {
+        let kind = if i.dynamic { "dynamic" } else { "static" };
+        let placement = if i.merged { "merged" } else { "file" };
+        format!("{}\t{}\t{kind}\t{placement}\n", i.shell, i.path.to_string_lossy())
+    }).collect();
+    std::fs::create_dir_all(marker.parent().expect("marker has a parent"))?;
+    std::fs::write(marker, lines.concat())
+}
+
+fn block_markers(bin_name: &str) -> (String, String) {
+    (format!("# >>> {bin_name} >>>"), format!("# <<< {bin_name} <<<"))
+}
+
+/// The text without the block between the markers and the empty line `merge_block` put before it.
+fn strip_block(existing: &str, bin_name: &str) -> Option<String> {
+    let (begin, end) = block_markers(bin_name);
+    let lines: Vec<&str> = existing.lines().collect();
+    let b = lines.iter().position(|l| l.trim_end() == begin)?;
+    let e = b + lines.iter().skip(b).position(|l| l.trim_end() == end)?;
+    let keep_until = if b > 0 && lines[b - 1].is_empty() { b - 1 } else { b };
+    let kept: Vec<&str> = lines[..keep_until].iter().chain(&lines[e + 1..]).copied().collect();
+    Some(kept.iter().map(|l| format!("{l}\n")).collect())
+}
+
+/// Replaces the block between the markers by the script, or appends it if there is none yet.
+fn merge_block(existing: &str, script: &str, bin_name: &str) -> String {
+    let (begin, end) = block_markers(bin_name);
+    let block = format!("{begin}\n{}\n{end}\n", script.trim_end());
+    let lines: Vec<&str> = existing.lines().collect();
+    let begin_at = lines.iter().position(|l| l.trim_end() == begin);
+    let end_at = begin_at.and_then(|b| lines.iter().skip(b).position(|l| l.trim_end() == end).map(|e| b + e));
+    match (begin_at, end_at) {
+        (Some(b), Some(e)) => {
+            let mut merged: String = lines[..b].iter().map(|l| format!("{l}\n")).collect();
+            merged.push_str(&block);
+            merged.extend(lines[e + 1..].iter().map(|l| format!("{l}\n")));
+            merged
+        },
+        _ if existing.is_empty() => block,
+        _ if existing.ends_with('\n') => format!("{existing}\n{block}"),
+        _ => format!("{existing}\n\n{block}"),
+    }
+}
+
+/// Shells for which `--for` prints the completion of a single script.
+pub const ARG_COMPLETION_SHELLS: [Shell; 2] = [Shell::Bash, Shell::Zsh];
+
+/// Completion of the arguments of a single script according to its `arg_spec`,
+/// meant to be plugged into an existing completion setup.
+pub fn print_arg_completions(shell: Shell, bin_name: &str, command: &crate::Cmd) {
+    let function = format!("_{bin_name}_{}", command.alias).replace(|c: char| !c.is_ascii_alphanumeric(), "_");
+    match shell {
+        Shell::Bash => {
+            let mut cases = String::new();
+            for (i, kind) in command.arg_spec.iter().enumerate() {
+                let action = match kind.as_str() {
+                    "dir" => "compgen -d -- \"$cur\"",
+                    "file" => "compgen -f -- \"$cur\"",
+                    _ => continue,
+                };
+                cases.push_str(&format!("        {}) COMPREPLY=($({action})) ;;\n", i + 1));
+            }
+            print!(r#"# completion of `{bin_name} {alias}` arguments
+{function}() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    local i position=0
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        if [ "${{COMP_WORDS[i]}}" = "{alias}" ]; then
+            position=$((COMP_CWORD - i))
+            break
+        fi
+    done
+    case $position in
+{cases}        *) COMPREPLY=() ;;
+    esac
+}}
+"#, alias = command.alias);
+        },
+        Shell::Zsh => {
+            let specs: Vec<String> = command.arg_spec.iter().enumerate().map(|(i, kind)| {
+                let action = match kind.as_str() {
+                    "dir" => "_files -/",
+                    "file" => "_files",
+                    _ => "_default",
+                };
+                format!("':arg{}:{action}'", i + 1)
+            }).collect();
+            print!("# completion of `{bin_name} {}` arguments\n{function}() {{\n    _arguments {}\n}}\n", command.alias, specs.join(" "));
+        },
+        _ => unreachable!("completion of a single script is not supported for {shell}"),
+    }
+}
+
+/// Prints `alias<TAB>description` per script, a stable format for completion scripts of other shells.
+pub fn print_command_lines(groups: &[crate::CmdGroup]) {
+    for command in groups.iter().flat_map(|g| &g.commands) {
+        let description = command.description.replace(['\t', '\n'], " ");
+        println!("{}\t{}", command.alias, description.trim_end());
+    }
+}
+
+/// Escapes the text for roff, a leading dot or quote would start a request.
+fn roff_escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    if escaped.starts_with(['.', '\'']) {
+        format!("\\&{escaped}")
+    } else {
+        escaped
+    }
+}
+
+/// Prints a roff manual page of the builtins, with `scripts` also a section listing the given scripts.
+pub fn print_man(bin_name: &str, scripts: Option<&[crate::CmdGroup]>) {
+    let man = clap_mangen::Man::new(crate::build_cli().name(bin_name.to_string()));
+    let mut out = std::io::stdout();
+    man.render(&mut out).expect("unable to write the manual page");
+    let Some(groups) = scripts else {
+        return;
+    };
+    let mut section = String::from(".SH SCRIPTS\n");
+    for command in groups.iter().flat_map(|g| &g.commands) {
+        let description = command.description.replace('\n', " ");
+        section.push_str(&format!(".TP\n\\fB{}\\fR ({})\n{}\n", roff_escape(&command.alias), command.scope.kind.name(), roff_escape(description.trim())));
+    }
+    write!(out, "{section}").expect("unable to write the manual page");
+}
+
+/// Copy of the cli where the builtins lose their `--init`-like aliases.
+///
+/// The zsh and fish generators treat words starting with a dash as options,
+/// so the dashed aliases would never complete nor would their arguments.
+fn without_dashed_aliases(cli: &Command) -> Command {
+    let subcommands: Vec<Command> = cli.get_subcommands().map(|subcommand| {
+        if subcommand.get_all_aliases().any(|a| a.starts_with('-')) {
+            with_about(Command::new(subcommand.get_name().to_string()), subcommand)
+                .disable_help_flag(true)
+                .args(subcommand.get_arguments().cloned())
+        } else {
+            subcommand.clone()
+        }
+    }).collect();
+    with_about(Command::new(cli.get_name().to_string()), cli)
+        .disable_help_flag(true)
+        .disable_help_subcommand(true)
+        .args(cli.get_arguments().cloned())
+        .subcommands(subcommands)
+}
+
+fn with_about(command: Command, source: &Command) -> Command {
+    match source.get_about() {
+        Some(about) => command.about(about.clone()),
+        None => command,
+    }
+}
+
+/// Names, aliases, and scope flags of the builtin commands, user scripts are excluded.
+fn builtin_words(cli: &Command) -> Vec<String> {
+    let mut words = vec![];
+    for arg in cli.get_arguments() {
+        if let Some(short) = arg.get_short() {
+            words.push(format!("-{short}"));
+        }
+        if let Some(long) = arg.get_long() {
+            words.push(format!("--{long}"));
+        }
+    }
+    for subcommand in cli.get_subcommands().filter(|s| !s.is_hide_set()) {
+        words.push(subcommand.get_name().to_string());
+        words.extend(subcommand.get_visible_aliases().map(|a| a.to_string()));
+    }
+    words
+}
+
+/// Bash completion that asks the binary for the current aliases when completing,
+/// so newly added scripts complete without regenerating the completion script.
+///
+/// The answer is kept in a cache file per scope, which is used only while it is newer than the
+/// `.cmd` folders and indexes of the local and the global scope, so repeated TABs do not start
+/// the binary while a change of the scripts is seen at once.
+///
+/// Words after a builtin are completed by the generated completion of the builtins, whose
+/// `complete` registration is then replaced by ours; after a script nothing is offered, so bash
+/// falls back to its default completion.
+fn dynamic_bash(bin_name: &str, builtins: &[String]) -> String {
+    let generated = builtin_completions(Shell::Bash, bin_name);
+    let delegate = generated.split_whitespace()
+        .skip_while(|word| *word != "-F")
+        .nth(1)
+        .expect("generated bash completions register no function")
+        .to_string();
+    let function = format!("_{}_dynamic", bin_name.replace(|c: char| !c.is_ascii_alphanumeric(), "_"));
+    let builtins = builtins.join(" ");
+    let global_dir = crate::shell_quote(&crate::path_to_str(&crate::find_global_dir()));
+    let indexes = crate::IndexFormat::ALL.map(|f| f.file_name()).join(" ");
+    format!(r#"{generated}
+{function}() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    local builtins="{builtins}"
+    local i scope=()
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        case "${{COMP_WORDS[i]}}" in
+            -l|--local|-g|--global) scope+=("${{COMP_WORDS[i]}}") ;;
+            *)
+                if [[ " $builtins " == *" ${{COMP_WORDS[i]}} "* ]]; then
+                    {delegate} "$@"
+                else
+                    COMPREPLY=()
+                fi
+                return ;;
+        esac
+    done
+    local root="$PWD"
+    until [[ -e "$root/.cmd" || -L "$root/.cmd" || -z "$root" ]]; do
+        root="${{root%/*}}"
+    done
+    local dir="${{XDG_CACHE_HOME:-$HOME/.cache}}/{bin_name}"
+    local key="$root ${{scope[*]}}"
+    key="${{key//\//%}}"
+    local cache="$dir/aliases-${{key// /%}}" aliases="" fresh=1 scope_dir index
+    for scope_dir in {global_dir} ${{root:+"$root"}}; do
+        for index in .cmd {indexes}; do
+            [[ "$index" == .cmd ]] || index=".cmd/$index"
+            [[ "$cache" -nt "$scope_dir/$index" ]] || fresh=""
+        done
+    done
+    if [[ -n "$fresh" ]]; then
+        IFS= read -r -d '' aliases < "$cache"
+    else
+        aliases="$({bin_name} "${{scope[@]}}" --list --plain 2>/dev/null)"
+        [[ -d "$dir" ]] || mkdir -p "$dir" 2>/dev/null
+        printf '%s\n' "$aliases" 2>/dev/null > "$cache"
+    fi
+    COMPREPLY=($(compgen -W "$builtins $aliases" -- "$cur"))
+}}
+
+complete -o default -F {function} {bin_name}
+"#)
+}
+
+/// Static completion of the builtins alone, to which the dynamic variants add the scripts.
+fn builtin_completions(shell: Shell, bin_name: &str) -> String {
+    let mut data = vec![];
+    clap_complete::generate(shell, &mut without_dashed_aliases(&crate::build_cli()), bin_name, &mut data);
+    String::from_utf8(data).expect("generated completions are not valid utf-8")
+}
+
+/// Zsh completion whose command list asks the binary for the scripts with their descriptions.
+///
+/// The generated `_<bin>_commands` is defined only if it does not exist yet, so it is replaced
+/// by defining ours first.
+fn dynamic_zsh(bin_name: &str) -> String {
+    let generated = builtin_completions(Shell::Zsh, bin_name);
+    let (first_line, rest) = generated.split_once('\n').unwrap_or(("", &generated));
+    let escape = |text: &str| text.replace('\\', "\\\\").replace(':', "\\:").replace('\'', "'\\''");
+    let builtins: String = crate::build_cli().get_subcommands()
+        .filter(|s| !s.is_hide_set())
+        .map(|s| format!("'{}:{}'\n", escape(s.get_name()), escape(&s.get_about().map(|a| a.to_string()).unwrap_or_default())))
+        .collect();
+    format!(r#"{first_line}
+
+_{bin_name}_commands() {{
+    local -a commands scope
+    local line tab=$'\t'
+    commands=(
+{builtins}    )
+    scope=(${{(M)words[@]:#(-l|--local|-g|--global)}})
+    for line in "${{(@f)$({bin_name} "${{scope[@]}}" complete-commands 2>/dev/null)}}"; do
+        [[ -n "$line" ]] && commands+=("${{${{line%%$tab*}}//:/\\:}}:${{line#*$tab}}")
+    done
+    _describe -t commands '{bin_name} commands' commands "$@"
+}}
+{rest}"#)
+}
+
+/// Fish completion which asks the binary for the scripts, fish shows the tab-separated descriptions.
+fn dynamic_fish(bin_name: &str) -> String {
+    let generated = builtin_completions(Shell::Fish, bin_name);
+    let condition = generated.lines()
+        .filter_map(|l| l.strip_prefix("function "))
+        .find(|name| name.ends_with("_needs_command"))
+        .unwrap_or("__fish_use_subcommand")
+        .to_string();
+    let function = format!("__{}_scripts", bin_name.replace(|c: char| !c.is_ascii_alphanumeric(), "_"));
+    format!(r#"{generated}
+function {function}
+    {bin_name} (commandline -opc | string match -r -- '^(-l|--local|-g|--global)$') complete-commands 2>/dev/null
+end
+complete -c {bin_name} -n "{condition}" -f -a "({function})"
+"#)
+}
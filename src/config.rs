@@ -0,0 +1,118 @@
+use anyhow::Result;
+use serde_derive::{Serialize, Deserialize};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::completions::config_home;
+use crate::path_to_str;
+
+/// Settings of the user which apply to all scopes.
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// Editor used instead of `$EDITOR`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+    /// Scope used without `-l` or `-g`, the local one if it exists when not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_scope: Option<String>,
+    /// Directory of the global scope instead of the one of the executable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub global_scope: Option<PathBuf>,
+}
+
+pub const KEYS: [&str; 3] = ["editor", "default-scope", "global-scope"];
+
+pub fn path() -> Option<PathBuf> {
+    config_home().map(|dir| dir.join(env!("CARGO_PKG_NAME")).join("config.toml"))
+}
+
+fn try_load() -> Result<Config> {
+    let Some(path) = path().filter(|p| p.exists()) else {
+        return Ok(Config::default());
+    };
+    let data = std::fs::read_to_string(&path)?;
+    toml::from_str(&data).map_err(|e| anyhow::anyhow!("{} is invalid: {e}", path_to_str(&path)))
+}
+
+/// The settings, defaults if there are none or they cannot be read; they are read once per run
+/// and problems are reported on stderr, as stdout may be parsed by completion scripts.
+pub fn load() -> &'static Config {
+    static CONFIG: OnceLock<Config> = OnceLock::new();
+    CONFIG.get_or_init(|| try_load().unwrap_or_else(|e| {
+        eprintln!("WARN: ignoring the config, {e}");
+        Config::default()
+    }))
+}
+
+pub fn save(config: &Config) -> Result<PathBuf> {
+    let path = path().ok_or(anyhow::anyhow!("neither XDG_CONFIG_HOME nor HOME is set"))?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, toml::to_string_pretty(config)?)?;
+    Ok(path)
+}
+
+impl Config {
+    fn get(&self, key: &str) -> Option<String> {
+        match key {
+            "editor" => self.editor.to_owned(),
+            "default-scope" => self.default_scope.to_owned(),
+            "global-scope" => self.global_scope.as_ref().map(path_to_str),
+            _ => unreachable!("unknown setting {key}"),
+        }
+    }
+
+    fn set(&mut self, key: &str, value: Option<&String>) -> Result<()> {
+        match key {
+            "editor" => self.editor = value.cloned(),
+            "default-scope" => {
+                if let Some(scope) = value.filter(|v| *v != "local" && *v != "global") {
+                    anyhow::bail!("default-scope is either local or global, not {scope}");
+                }
+                self.default_scope = value.cloned();
+            },
+            "global-scope" => {
+                self.global_scope = match value {
+                    Some(dir) => {
+                        let dir = std::fs::canonicalize(dir).map_err(|e| anyhow::anyhow!("unable to use {dir} as the global scope: {e}"))?;
+                        if !dir.is_dir() {
+                            anyhow::bail!("{} is not a directory", path_to_str(&dir));
+                        }
+                        Some(dir)
+                    },
+                    None => None,
+                };
+            },
+            _ => unreachable!("unknown setting {key}"),
+        }
+        Ok(())
+    }
+}
+
+/// Prints the setting, or all of them with `list`; given a value, or `unset`, changes it instead.
+pub fn cmd_config(key: Option<&String>, value: Option<&String>, unset: bool, list: bool) {
+    let mut config = try_load().unwrap_or_else(|e| panic!("{e}"));
+    let Some(key) = key.filter(|_| !list) else {
+        for key in KEYS {
+            println!("{key} = {}", config.get(key).unwrap_or_default());
+        }
+        return;
+    };
+    if value.is_none() && !unset {
+        match config.get(key) {
+            Some(value) => println!("{value}"),
+            None => std::process::exit(1),
+        }
+        return;
+    }
+    if let Err(e) = config.set(key, value) {
+        println!("ERR: {e}");
+        std::process::exit(1);
+    }
+    if let Err(e) = save(&config) {
+        println!("ERR: unable to save the config: {e}");
+        std::process::exit(1);
+    }
+}
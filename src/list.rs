@@ -0,0 +1,209 @@
+use clap::ArgMatches;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{Cmd, CmdGroup, UNGROUPED};
+
+pub struct ListOptions {
+    plain: bool,
+    /// Width to fit the descriptions into, `None` disables truncation.
+    width: Option<usize>,
+    /// Show only this many most recently run scripts.
+    recent: Option<usize>,
+    /// Show only this many most often run scripts.
+    popular: Option<usize>,
+    /// Show only scripts of this group.
+    group: Option<String>,
+    /// Show also size, modification time, and line count of the scripts.
+    long: bool,
+    /// Print the usage of each script, from its `usage` or `arg_spec`.
+    usage: bool,
+    sort: Option<String>,
+    /// Fields shown for each script in this order, instead of the alias, scope, and description.
+    columns: Option<Vec<String>>,
+}
+
+impl ListOptions {
+    pub fn new(matched_args: &ArgMatches) -> ListOptions {
+        let width = if matched_args.get_flag("full") {
+            None
+        } else if let Some(width) = matched_args.get_one::<usize>("width") {
+            Some(*width)
+        } else {
+            terminal_size::terminal_size().map(|(terminal_size::Width(w), _)| w as usize)
+        };
+        ListOptions{
+            plain: matched_args.get_flag("plain"),
+            width,
+            recent: matched_args.get_one::<usize>("recent").copied(),
+            popular: matched_args.get_one::<usize>("popular").copied(),
+            group: matched_args.get_one::<String>("group").cloned(),
+            long: matched_args.get_flag("long"),
+            usage: matched_args.get_flag("usage"),
+            sort: matched_args.get_one::<String>("sort").cloned(),
+            columns: matched_args.get_many::<String>("columns").map(|c| c.cloned().collect()),
+        }
+    }
+}
+
+/// The declared usage, otherwise one derived from the kinds of the arguments, empty if there is neither.
+fn usage(command: &Cmd) -> String {
+    match &command.usage {
+        Some(usage) => usage.to_owned(),
+        None => command.arg_spec.iter()
+            .map(|kind| if kind == "any" { "<arg>".to_string() } else { format!("<{kind}>") })
+            .collect::<Vec<String>>()
+            .join(" "),
+    }
+}
+
+/// Value of the `--columns` field of the script.
+fn cell(command: &Cmd, column: &str) -> String {
+    match column {
+        "alias" => command.alias.to_owned(),
+        "scope" => command.scope.kind.name().to_string(),
+        "description" => command.description.to_owned(),
+        "path" => crate::path_to_str(&crate::normalize(&command.abs_path)),
+        "tags" => command.tags.join(","),
+        _ => unreachable!("unknown column {column}"),
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+fn modified_at(path: &PathBuf) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+struct FileStats {
+    size: u64,
+    modified: Option<SystemTime>,
+    lines: usize,
+}
+
+fn file_stats(path: &PathBuf) -> Option<FileStats> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let lines = std::fs::read(path).map(|data| data.iter().filter(|b| **b == b'\n').count()).unwrap_or(0);
+    Some(FileStats{
+        size: metadata.len(),
+        modified: metadata.modified().ok(),
+        lines,
+    })
+}
+
+/// Formats the time as `YYYY-MM-DD HH:MM` in UTC.
+pub fn format_time(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, day_secs) = ((secs / 86400) as i64, secs % 86400);
+    // civil date from days since the epoch, see Howard Hinnant's date algorithms
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{year:04}-{month:02}-{day:02} {:02}:{:02}", day_secs / 3600, day_secs % 3600 / 60)
+}
+
+pub fn cmd_list(options: &ListOptions, groups: &[CmdGroup]) {
+    let mut commands: Vec<&Cmd> = groups.iter().flat_map(|g| &g.commands).collect();
+    if let Some(group) = &options.group {
+        commands.retain(|c| c.group_name() == group);
+    }
+    match options.sort.as_deref() {
+        Some("alias") => commands.sort_by(|a, b| a.alias.cmp(&b.alias)),
+        Some("size") => commands.sort_by_cached_key(|c| std::cmp::Reverse(file_stats(&c.abs_path).map(|s| s.size))),
+        Some("updated") => commands.sort_by_cached_key(|c| std::cmp::Reverse((c.updated_at, modified_at(&c.abs_path)))),
+        _ => {},
+    }
+    if let Some(count) = options.recent {
+        // scripts which were never run follow by the time their file was changed
+        commands.sort_by_cached_key(|c| std::cmp::Reverse((c.last_run, modified_at(&c.abs_path))));
+        commands.truncate(count);
+    }
+    if let Some(count) = options.popular {
+        // ties keep the order of the index
+        commands.sort_by_key(|c| std::cmp::Reverse(c.run_count));
+        commands.truncate(count);
+    }
+    if options.plain {
+        for command in commands {
+            println!("{}", command.alias);
+        }
+        return;
+    }
+    let width = commands.iter().map(|c| c.alias.len()).max().unwrap_or(0);
+    if options.usage {
+        for command in commands {
+            println!("{}", format!("{:width$}  {}", command.alias, usage(command)).trim_end());
+        }
+        return;
+    }
+    let widths: Vec<usize> = options.columns.iter().flatten()
+        .map(|column| commands.iter().map(|c| cell(c, column).chars().count()).max().unwrap_or(0))
+        .collect();
+    let print_line = |command: &Cmd| {
+        if let Some(columns) = &options.columns {
+            let mut line = String::new();
+            for (i, column) in columns.iter().enumerate() {
+                let value = cell(command, column);
+                if i + 1 < columns.len() {
+                    line.push_str(&format!("{value:0$}  ", widths[i]));
+                } else if let Some(total) = options.width {
+                    line.push_str(&truncate(&value, total.saturating_sub(line.chars().count())));
+                } else {
+                    line.push_str(&value);
+                }
+            }
+            println!("{}", line.trim_end());
+            return;
+        }
+        let mut line = format!("{:width$}  {:6}  ", command.alias, command.scope.kind.name());
+        if options.long {
+            let columns = match file_stats(&command.abs_path) {
+                Some(stats) => {
+                    let modified = stats.modified.map(format_time).unwrap_or("—".to_string());
+                    format!("{:>8}  {:16}  {:>5}  ", stats.size, modified, stats.lines)
+                },
+                None => format!("{:>8}  {:16}  {:>5}  ", "—", "—", "—"),
+            };
+            line.push_str(&columns);
+        }
+        let description = match options.width {
+            Some(total) => truncate(&command.description, total.saturating_sub(line.chars().count())),
+            None => command.description.to_owned(),
+        };
+        line.push_str(&description);
+        println!("{}", line.trim_end());
+    };
+    let grouped = commands.iter().any(|c| c.group.is_some());
+    if !grouped || options.recent.is_some() || options.popular.is_some() || options.group.is_some() {
+        commands.into_iter().for_each(print_line);
+        return;
+    }
+    let mut sections: Vec<(&str, Vec<&Cmd>)> = vec![];
+    for command in commands {
+        match sections.iter_mut().find(|(name, _)| *name == command.group_name()) {
+            Some((_, section)) => section.push(command),
+            None => sections.push((command.group_name(), vec![command])),
+        }
+    }
+    sections.sort_by_key(|(name, _)| *name == UNGROUPED);
+    for (i, (name, section)) in sections.into_iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{name}:");
+        section.into_iter().for_each(print_line);
+    }
+}
@@ -1,19 +1,219 @@
-use clap::{arg, command, Command, ArgMatches, ValueHint};
+mod backup;
+mod completions;
+mod config;
+mod list;
+mod runs;
+mod validate;
+
+use clap::{arg, command, Arg, ArgAction, Command, ArgMatches, ValueHint};
+use clap_complete::Shell;
 use anyhow::Result;
-use std::{io::Write, path::PathBuf};
+use std::{io::{IsTerminal, Read, Write}, path::{Path, PathBuf}};
 use serde_derive::{Serialize, Deserialize};
-use std::os::unix::fs::PermissionsExt;
-
-fn execute(cmd: &String, args: impl IntoIterator<Item = String>) {
-    let status = std::process::Command::new(cmd)
-        .args(args)
-        .spawn()
-        .expect(format!("ERROR: Failed to execute command {cmd}").as_str())
-        .wait()
-        .expect("error executing command");
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Child, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// With a timeout the program gets its own process group, so that it is stopped together with its
+/// children. On a terminal it has to stay in the foreground group to read the input and get Ctrl-C,
+/// so there only the program itself is stopped, like by `timeout --foreground`.
+fn own_process_group(timeout: Option<Duration>) -> bool {
+    timeout.is_some() && !std::io::stdin().is_terminal()
+}
+
+fn process(cmd: &String, args: impl IntoIterator<Item = String>, envs: &[(String, String)], timeout: Option<Duration>) -> std::process::Command {
+    let mut process = std::process::Command::new(cmd);
+    process.args(args).envs(envs.iter().cloned());
+    if own_process_group(timeout) {
+        process.process_group(0);
+    }
+    process
+}
+
+/// Process group of the running program, which receives the signals sent to scriptman meanwhile.
+static CHILD_GROUP: AtomicI32 = AtomicI32::new(0);
+
+const FORWARDED_SIGNALS: [libc::c_int; 3] = [libc::SIGINT, libc::SIGTERM, libc::SIGHUP];
+
+extern "C" fn forward_signal(signal: libc::c_int) {
+    let group = CHILD_GROUP.load(Ordering::SeqCst);
+    if group > 0 {
+        unsafe { libc::kill(-group, signal); }
+    }
+}
+
+/// Sends the signals of scriptman to the process group while it runs, so it is not left orphaned.
+fn forward_signals(group: Option<u32>) {
+    CHILD_GROUP.store(group.unwrap_or(0) as i32, Ordering::SeqCst);
+    let handler = if group.is_some() { forward_signal as extern "C" fn(libc::c_int) as libc::sighandler_t } else { libc::SIG_DFL };
+    for signal in FORWARDED_SIGNALS {
+        unsafe { libc::signal(signal, handler); }
+    }
+}
+
+fn execute(cmd: &String, args: impl IntoIterator<Item = String>, envs: &[(String, String)], timeout: Option<Duration>) -> std::io::Result<ExitStatus> {
+    let mut child = process(cmd, args, envs, timeout).spawn()?;
+    let status = wait_with_timeout(&mut child, timeout)?;
+    if !status.success(){
+        println!("INFO: Program exited with code: {status}");
+    }
+    Ok(status)
+}
+
+/// Like `execute`, but also stores the stdout and stderr of the program into the given files.
+fn execute_captured(cmd: &String, args: impl IntoIterator<Item = String>, envs: &[(String, String)], timeout: Option<Duration>, out_path: &Path, err_path: &Path, quiet: bool) -> std::io::Result<ExitStatus> {
+    let status = run_captured(cmd, args, envs, timeout, out_path, err_path, quiet)?;
     if !status.success(){
         println!("INFO: Program exited with code: {status}");
     }
+    Ok(status)
+}
+
+fn run_captured(cmd: &String, args: impl IntoIterator<Item = String>, envs: &[(String, String)], timeout: Option<Duration>, out_path: &Path, err_path: &Path, quiet: bool) -> std::io::Result<ExitStatus> {
+    let out_file = std::fs::File::create(out_path)?;
+    let err_file = std::fs::File::create(err_path)?;
+    let mut child = process(cmd, args, envs, timeout)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let out = tee(child.stdout.take().expect("piped stdout"), out_file, (!quiet).then(std::io::stdout));
+    let err = tee(child.stderr.take().expect("piped stderr"), err_file, (!quiet).then(std::io::stderr));
+    let status = wait_with_timeout(&mut child, timeout);
+    out.join().expect("stdout capture thread panicked")?;
+    err.join().expect("stderr capture thread panicked")?;
+    status
+}
+
+/// Output larger than this is left in a file which `--capture-json` refers to instead of including it.
+const JSON_OUTPUT_LIMIT: u64 = 1 << 20;
+
+/// Result of a run printed by `--capture-json`.
+type JsonResult = serde_json::Map<String, serde_json::Value>;
+
+/// Runs the program with its output captured into a temporary directory and returns the result.
+fn execute_json(alias: &str, cmd: &String, args: Vec<String>, options: &RunOptions) -> (ExitStatus, JsonResult) {
+    let dir = std::env::temp_dir().join(format!("{}-{}", env!("CARGO_PKG_NAME"), std::process::id()));
+    std::fs::create_dir_all(&dir).expect("unable to create a temporary directory for the output");
+    let out_path = dir.join(format!("{}.out", file_stem(alias)));
+    let err_path = dir.join(format!("{}.err", file_stem(alias)));
+    let start = Instant::now();
+    let result = run_captured(cmd, args, &options.envs, options.timeout, &out_path, &err_path, true);
+    let duration_ms = start.elapsed().as_millis() as u64;
+    let (status, timed_out) = match result {
+        Ok(status) => (status, false),
+        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => (ExitStatus::from_raw(124 << 8), true),
+        Err(e) => panic!("ERROR: Failed to execute command {cmd}: {e}"),
+    };
+    let output = |path: &Path, name: &str, result: &mut JsonResult| {
+        if std::fs::metadata(path).map(|m| m.len()).unwrap_or(0) > JSON_OUTPUT_LIMIT {
+            result.insert(name.to_string(), serde_json::Value::Null);
+            result.insert(format!("{name}_file"), path_to_str(&path.to_path_buf()).into());
+        } else {
+            let data = std::fs::read(path).unwrap_or_default();
+            result.insert(name.to_string(), String::from_utf8_lossy(&data).into());
+            let _ = std::fs::remove_file(path);
+        }
+    };
+    let mut result = JsonResult::new();
+    result.insert("alias".to_string(), alias.into());
+    result.insert("exit_code".to_string(), status.code().into());
+    output(&out_path, "stdout", &mut result);
+    output(&err_path, "stderr", &mut result);
+    result.insert("duration_ms".to_string(), duration_ms.into());
+    if timed_out {
+        result.insert("timed_out".to_string(), true.into());
+    }
+    let _ = std::fs::remove_dir(&dir);
+    (status, result)
+}
+
+fn tee<R, W>(mut from: R, mut file: std::fs::File, mut echo: Option<W>) -> JoinHandle<std::io::Result<()>>
+where R: Read + Send + 'static, W: Write + Send + 'static {
+    std::thread::spawn(move || {
+        let mut buffer = [0u8; 8192];
+        loop {
+            let size = from.read(&mut buffer)?;
+            if size == 0 {
+                return Ok(());
+            }
+            file.write_all(&buffer[..size])?;
+            if let Some(echo) = &mut echo {
+                echo.write_all(&buffer[..size])?;
+                echo.flush()?;
+            }
+        }
+    })
+}
+
+/// Time between SIGTERM and SIGKILL of a timed out script.
+const KILL_GRACE: Duration = Duration::from_secs(5);
+
+/// Waits for the child, past the timeout it is terminated and a `TimedOut` error is returned.
+fn wait_with_timeout(child: &mut Child, timeout: Option<Duration>) -> std::io::Result<ExitStatus> {
+    let own_group = own_process_group(timeout);
+    let Some(timeout) = timeout else {
+        return child.wait();
+    };
+    if own_group {
+        forward_signals(Some(child.id()));
+    }
+    let result = wait_or_stop(child, timeout, own_group);
+    if own_group {
+        forward_signals(None);
+    }
+    result
+}
+
+fn wait_or_stop(child: &mut Child, timeout: Duration, own_group: bool) -> std::io::Result<ExitStatus> {
+    let poll = |child: &mut Child, limit: Duration| -> std::io::Result<Option<ExitStatus>> {
+        let start = Instant::now();
+        loop {
+            if let Some(status) = child.try_wait()? {
+                return Ok(Some(status));
+            }
+            if start.elapsed() >= limit {
+                return Ok(None);
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    };
+    if let Some(status) = poll(child, timeout)? {
+        return Ok(status);
+    }
+    let target = if own_group { -(child.id() as libc::pid_t) } else { child.id() as libc::pid_t };
+    unsafe { libc::kill(target, libc::SIGTERM); }
+    if poll(child, KILL_GRACE)?.is_none() {
+        unsafe { libc::kill(target, libc::SIGKILL); }
+        child.wait()?;
+    }
+    Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "timed out"))
+}
+
+struct RunOptions {
+    capture: Option<PathBuf>,
+    quiet: bool,
+    via_shell: bool,
+    /// Variables of the `--env-file` followed by the `--env` ones, so that the explicit ones win.
+    envs: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    /// Print the result of each run as a json line instead of passing the output through.
+    json: bool,
+}
+
+impl RunOptions {
+    fn new(cli_args: &ArgMatches) -> RunOptions {
+        RunOptions{
+            capture: cli_args.get_one::<PathBuf>("capture").cloned(),
+            quiet: cli_args.get_flag("quiet"),
+            via_shell: cli_args.get_flag("via-shell"),
+            envs: run_envs(cli_args),
+            timeout: cli_args.get_one::<u64>("timeout").map(|s| Duration::from_secs(*s)),
+            json: cli_args.get_flag("capture-json"),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -21,25 +221,80 @@ struct JsonCmd {
     alias: String,
     rel_path: String,
     description: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    arg_spec: Vec<String>,
+    /// Only read, the runs were recorded in the index before they got their own file.
+    #[serde(default, skip_serializing)]
+    last_run: Option<u64>,
+    #[serde(default, skip_serializing)]
+    run_count: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    updated_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    group: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    depends: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    readonly: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    usage: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    default_args: Vec<String>,
 }
 
 impl JsonCmd {
     fn to_cmd(&self, scope: &Scope) -> Cmd {
+        let runs = runs::of(&scope.path, &self.rel_path).unwrap_or(runs::Runs{last: self.last_run, count: self.run_count});
         Cmd{
             alias: self.alias.to_owned(),
             rel_path: self.rel_path.to_owned(),
             description: self.description.to_owned(),
-            abs_path: scope.path.join(self.rel_path.to_owned()),
+            arg_spec: self.arg_spec.to_owned(),
+            last_run: runs.last,
+            run_count: runs.count,
+            updated_at: self.updated_at,
+            group: self.group.to_owned(),
+            depends: self.depends.to_owned(),
+            readonly: self.readonly,
+            tags: self.tags.to_owned(),
+            usage: self.usage.to_owned(),
+            default_args: self.default_args.to_owned(),
+            abs_path: scope.path.join(&self.rel_path),
             scope: scope.to_owned(),
         }
     }
 }
 
+/// Group of the scripts which have none set.
+const UNGROUPED: &str = "misc";
+
 #[derive(Clone, Debug)]
 struct Cmd {
     alias: String,
     rel_path: String,
     description: String,
+    /// Kinds of the positional arguments (`file`, `dir`, or `any`) used for their completion.
+    arg_spec: Vec<String>,
+    /// Seconds since the unix epoch when the script was last run through scriptman.
+    last_run: Option<u64>,
+    /// Number of times the script was run through scriptman.
+    run_count: u64,
+    /// Seconds since the unix epoch when the script was last marked fresh by `touch`.
+    updated_at: Option<u64>,
+    /// Category under which the script is listed.
+    group: Option<String>,
+    /// Aliases of scripts which have to succeed before this one is run.
+    depends: Vec<String>,
+    /// Editing the script requires `--force` and its file is not writable by the owner.
+    readonly: bool,
+    /// Labels selecting the script for `run-all`.
+    tags: Vec<String>,
+    /// How the script is called, e.g. `<file> [--force]`.
+    usage: Option<String>,
+    /// Arguments passed to the script when it is run without any.
+    default_args: Vec<String>,
     abs_path: PathBuf,
     scope: Scope,
 }
@@ -50,8 +305,22 @@ impl Cmd {
             alias: alias.to_owned(),
             rel_path: rel_path.to_owned(),
             description: description.to_owned(),
+            arg_spec: vec![],
+            last_run: None,
+            run_count: 0,
+            updated_at: None,
+            group: None,
+            depends: vec![],
+            readonly: false,
+            tags: vec![],
+            usage: None,
+            default_args: vec![],
         }.to_cmd(scope)
     }
+
+    fn group_name(&self) -> &str {
+        self.group.as_deref().unwrap_or(UNGROUPED)
+    }
 }
 
 impl From<&Cmd> for JsonCmd {
@@ -60,6 +329,16 @@ impl From<&Cmd> for JsonCmd {
             alias: item.alias.to_owned(),
             rel_path: item.rel_path.to_owned(),
             description: item.description.to_owned(),
+            arg_spec: item.arg_spec.to_owned(),
+            last_run: item.last_run,
+            run_count: item.run_count,
+            updated_at: item.updated_at,
+            group: item.group.to_owned(),
+            depends: item.depends.to_owned(),
+            readonly: item.readonly,
+            tags: item.tags.to_owned(),
+            usage: item.usage.to_owned(),
+            default_args: item.default_args.to_owned(),
         }
     }
 }
@@ -72,7 +351,7 @@ struct CmdGroup {
 
 impl CmdGroup {
     fn new(scope: &Scope) -> Result<CmdGroup> {
-        let command_path = scope.path.join(".cmd").join("index.json").to_owned();
+        let command_path = find_index(&scope.path)?.unwrap_or(scope.path.join(".cmd").join(IndexFormat::Json.file_name()));
         let commands = load_from_file(&command_path)?.into_iter().map(|c|c.to_cmd(scope)).collect();
         Ok(CmdGroup{
             commands,
@@ -81,23 +360,121 @@ impl CmdGroup {
     }
 }
 
-fn save_to_file(path: &PathBuf, cmd_group: &CmdGroup) {
-    let commands = &cmd_group.commands;
-    let json_commands: Vec<JsonCmd> = commands.into_iter().map(|c|c.into()).collect();
-    let data = serde_json::to_string_pretty(&json_commands).expect("unable to jsonify data");
-    std::fs::write(path, data).expect("unable to save the index file");
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum IndexFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+/// TOML documents cannot have an array at the top level, so the commands are nested in a table.
+#[derive(Serialize, Deserialize)]
+struct TomlIndex {
+    commands: Vec<JsonCmd>,
+}
+
+impl IndexFormat {
+    const ALL: [IndexFormat; 3] = [IndexFormat::Json, IndexFormat::Yaml, IndexFormat::Toml];
+
+    fn file_name(&self) -> &'static str {
+        match self {
+            IndexFormat::Json => "index.json",
+            IndexFormat::Yaml => "index.yaml",
+            IndexFormat::Toml => "index.toml",
+        }
+    }
+
+    fn of(path: &Path) -> IndexFormat {
+        let file_name = path.file_name().and_then(|f| f.to_str());
+        IndexFormat::ALL.into_iter().find(|f| Some(f.file_name()) == file_name).unwrap_or(IndexFormat::Json)
+    }
+
+    fn serialize(&self, commands: &[JsonCmd]) -> Result<String> {
+        Ok(match self {
+            IndexFormat::Json => serde_json::to_string_pretty(commands)?,
+            IndexFormat::Yaml => serde_yaml::to_string(commands)?,
+            IndexFormat::Toml => toml::to_string_pretty(&TomlIndex{commands: commands.to_vec()})?,
+        })
+    }
+
+    fn deserialize(&self, data: &str) -> Result<Vec<JsonCmd>> {
+        Ok(match self {
+            IndexFormat::Json => serde_json::from_str::<Vec<JsonCmd>>(data)?,
+            IndexFormat::Yaml => serde_yaml::from_str::<Vec<JsonCmd>>(data)?,
+            IndexFormat::Toml => toml::from_str::<TomlIndex>(data)?.commands,
+        })
+    }
+}
+
+/// Index file of the scope in any of the supported formats, fails if there are several of them.
+fn find_index(scope_path: &Path) -> Result<Option<PathBuf>> {
+    let cmd_dir = scope_path.join(".cmd");
+    check_symlinks(&cmd_dir)?;
+    let mut found: Vec<PathBuf> = IndexFormat::ALL.iter()
+        .map(|f| cmd_dir.join(f.file_name()))
+        .filter(|p| p.exists())
+        .collect();
+    if found.len() > 1 {
+        let names: Vec<String> = found.iter().map(path_to_str).collect();
+        anyhow::bail!("multiple index files found, keep only one of: {}", names.join(", "));
+    }
+    Ok(found.pop())
+}
+
+/// A symlinked `.cmd` (or its `scripts`) is used transparently, but a dangling one is an error.
+fn check_symlinks(cmd_dir: &Path) -> Result<()> {
+    for dir in [cmd_dir.to_path_buf(), cmd_dir.join("scripts")] {
+        let is_symlink = dir.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink());
+        if is_symlink && !dir.exists() {
+            let target = std::fs::read_link(&dir).map(|t| path_to_str(&t)).unwrap_or_default();
+            anyhow::bail!("'{}' is a symlink to '{}' which does not exist", path_to_str(&dir), target);
+        }
+    }
+    Ok(())
+}
+
+fn index_path(scope_path: &Path) -> PathBuf {
+    match find_index(scope_path) {
+        Ok(Some(path)) => path,
+        Ok(None) => scope_path.join(".cmd").join(IndexFormat::Json.file_name()),
+        Err(e) => panic!("{e}"),
+    }
+}
+
+fn save_to_file(path: &Path, cmd_group: &CmdGroup) {
+    try_save_to_file(path, cmd_group).expect("unable to save the index file");
+}
+
+fn try_save_to_file(path: &Path, cmd_group: &CmdGroup) -> Result<()> {
+    write_replacing(path, serialize_group(path, cmd_group)?)
+}
+
+/// Writes the file through a temporary one so that a failed write leaves the original intact,
+/// the name of the temporary file is unique so that concurrent writes do not mix.
+fn write_replacing(path: &Path, data: String) -> Result<()> {
+    // write to the symlink target, renaming over the symlink would replace it
+    let path = &std::fs::canonicalize(path).unwrap_or(path.to_owned());
+    let suffix = std::hash::BuildHasher::hash_one(&std::collections::hash_map::RandomState::new(), Instant::now());
+    let tmp_path = path.with_extension(format!("{}-{suffix:x}.tmp", std::process::id()));
+    std::fs::write(&tmp_path, data)?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+fn serialize_group(path: &Path, cmd_group: &CmdGroup) -> Result<String> {
+    let json_commands: Vec<JsonCmd> = cmd_group.commands.iter().map(|c|c.into()).collect();
+    IndexFormat::of(path).serialize(&json_commands)
 }
 
 fn load_from_file(path: &PathBuf) -> Result<Vec<JsonCmd>> {
     let data = std::fs::read_to_string(path)?;
-    let commands = serde_json::from_str::<Vec<JsonCmd>>(&data)?;
-    Ok(commands)
+    IndexFormat::of(path).deserialize(&data)
 }
 
 fn find_local_dir() -> Option<PathBuf> {
     let mut dir: PathBuf = std::env::current_dir().unwrap();
     loop {
-        if dir.join(".cmd").exists() {
+        if dir.join(".cmd").symlink_metadata().is_ok() {
             return Some(dir.to_path_buf());
         }
         if !dir.pop() {
@@ -110,12 +487,22 @@ fn path_to_str(path: &PathBuf) -> String {
     path.to_owned().into_os_string().into_string().expect("unable to convert path to string")
 }
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(PartialEq, Clone, Debug)]
 enum ScopeKind {
     GLOBAL,
     LOCAL,
 }
 
+impl ScopeKind {
+    fn name(&self) -> &'static str {
+        match self {
+            ScopeKind::GLOBAL => "global",
+            ScopeKind::LOCAL => "local",
+        }
+    }
+}
+
 #[derive(PartialEq, Clone, Debug)]
 struct Scope{
     kind: ScopeKind,
@@ -123,7 +510,8 @@ struct Scope{
 }
 
 fn choose_scope(cli_args: &ArgMatches, global: Scope, local: Option<Scope>) -> Scope {
-    if cli_args.get_flag("global") {
+    let default_global = config::load().default_scope.as_deref() == Some("global");
+    if cli_args.get_flag("global") || (default_global && !cli_args.get_flag("local")) {
         global
     } else {
         match local {
@@ -139,26 +527,31 @@ fn choose_scope(cli_args: &ArgMatches, global: Scope, local: Option<Scope>) -> S
 }
 
 fn find_global_dir() -> PathBuf {
+    if let Some(dir) = config::load().global_scope.to_owned() {
+        return dir;
+    }
     match std::env::current_exe() {
         Ok(mut dir) => { dir.pop(); dir }
         Err(e) => panic!("cannot retrieve directory of the executable -- place for the global scope scripts: {e}"),
     }
 }
 
-fn ensure_initialized(path: &PathBuf, report: bool) -> PathBuf {
+fn ensure_initialized(path: &Path, report: bool) -> PathBuf {
     let cmd_dir = path.join(".cmd");
-    if let Err(_) = std::fs::create_dir(&cmd_dir) {
-        if report { println!("INFO: ./.cmd/ folder already exists"); }
+    check_symlinks(&cmd_dir).unwrap_or_else(|e| panic!("{e}"));
+    if std::fs::create_dir(&cmd_dir).is_err() && report {
+        println!("INFO: ./.cmd/ folder already exists");
     }
     let cmd_subdir = cmd_dir.join("scripts");
-    if let Err(_) = std::fs::create_dir(&cmd_subdir) {
-        if report { println!("INFO: ./.cmd/scripts/ folder already exists"); }
+    if std::fs::create_dir(&cmd_subdir).is_err() && report {
+        println!("INFO: ./.cmd/scripts/ folder already exists");
     }
-    let file_path = cmd_dir.join("index.json");
-    if file_path.exists() {
-        if report { println!("INFO ./.cmd/index.json file already exists"); }
+    if let Some(file_path) = find_index(path).unwrap_or_else(|e| panic!("{e}")) {
+        let file_name = IndexFormat::of(&file_path).file_name();
+        if report { println!("INFO ./.cmd/{file_name} file already exists"); }
         return file_path;
     }
+    let file_path = cmd_dir.join(IndexFormat::Json.file_name());
     if let Ok(mut file) = std::fs::File::create(&file_path){
         file.write_all(b"[]").expect("unable to write into file");
     } else {
@@ -167,198 +560,2014 @@ fn ensure_initialized(path: &PathBuf, report: bool) -> PathBuf {
     file_path
 }
 
-fn cmd_init_local() {
+fn cmd_init_local(git: bool, git_init: bool) {
     let current_dir: PathBuf = std::env::current_dir().expect("unable to retrieve current directory");
     ensure_initialized(&current_dir, true);
+    if git || git_init {
+        commit_scope(&current_dir, git_init);
+    }
 }
 
-fn cmd_add(alias: &String, description: &String, scope: &Scope, groups: &mut Vec<CmdGroup>) {
-    let some_command = find_command(&alias, &groups);
-    if let None = some_command {
-        let rel_path = format!("./.cmd/scripts/{alias}.sh");
-        let res: Option<&mut CmdGroup> = get_group_mut(&scope.kind, groups);
-        if let Some(&mut ref mut group) = res{
-            let command = Cmd::new(alias, &rel_path, description, &group.scope);
-            let commands_file = ensure_initialized(&scope.path, false);
-            if !command.abs_path.exists() {
-                let mut file = std::fs::File::create(&command.abs_path).expect("unable to create file");
-                file.write_all(b"#!/usr/bin/env sh\n\necho \"Hello world\"\n").expect("unable to write into file");
-                std::fs::set_permissions(&command.abs_path, std::fs::Permissions::from_mode(0o775)).expect("unable to assign script permissions");
-            }
-            group.commands.push(command.to_owned());
-            save_to_file(&commands_file, &group);
-            edit_file(&command.abs_path);
+/// Reports what `init` would create in the current directory without touching it.
+fn cmd_init_dry_run() {
+    let current_dir: PathBuf = std::env::current_dir().expect("unable to retrieve current directory");
+    let cmd_dir = current_dir.join(".cmd");
+    check_symlinks(&cmd_dir).unwrap_or_else(|e| panic!("{e}"));
+    let report = |name: &str, exists: bool| {
+        println!("{}: ./.cmd/{name}", if exists { "exists" } else { "would create" });
+    };
+    report("", cmd_dir.exists());
+    report("scripts/", cmd_dir.join("scripts").exists());
+    match find_index(&current_dir).unwrap_or_else(|e| panic!("{e}")) {
+        Some(path) => report(IndexFormat::of(&path).file_name(), true),
+        None => report(IndexFormat::Json.file_name(), false),
+    }
+}
+
+fn is_git_repo(path: &Path) -> bool {
+    path.ancestors().any(|dir| dir.join(".git").exists())
+}
+
+fn run_git(path: &Path, args: &[&str]) -> bool {
+    let mut git_args = vec!["-C".to_string(), path_to_str(&path.to_path_buf())];
+    git_args.extend(args.iter().map(|a| a.to_string()));
+    match execute(&"git".into(), git_args, &[], None) {
+        Ok(status) => status.success(),
+        Err(e) => {
+            println!("WARN: unable to run git: {e}");
+            false
+        },
+    }
+}
+
+/// Stages and commits the `.cmd` folder; failures only warn as the scope itself is already set up.
+fn commit_scope(path: &Path, git_init: bool) {
+    if git_init {
+        if !run_git(path, &["init"]) {
+            println!("WARN: git init failed, the scope is initialized but not committed");
+            return;
         }
-    } else  {
-        panic!("unable to create {alias} because it already exists");
+    } else if !is_git_repo(path) {
+        println!("WARN: not inside a git repository, use --git-init to create one");
+        return;
+    }
+    if !run_git(path, &["add", ".cmd"]) {
+        println!("WARN: unable to stage ./.cmd/, the scope is initialized but not committed");
+        return;
+    }
+    if !run_git(path, &["commit", "-m", "Initialize cmd scope", "--", ".cmd"]) {
+        println!("WARN: unable to commit ./.cmd/, it is left staged");
     }
 }
 
-fn cmd_edit(some_alias: Option<&String>, scope: &Scope, cmd_groups: &Vec<CmdGroup>) {
-    if let Some(alias) = some_alias{
-        if let Some(command) = find_command(&alias, &cmd_groups) {
-            edit_file(&command.abs_path);
-        } else {
-            println!("{alias} is an unknown command");
+#[derive(Default, Clone)]
+struct AddOptions {
+    arg_spec: Vec<String>,
+    group: Option<String>,
+    depends: Vec<String>,
+    readonly: bool,
+    tags: Vec<String>,
+    /// Permissions of the created script, 0o775 when not given.
+    mode: Option<u32>,
+    usage: Option<String>,
+    default_args: Vec<String>,
+    /// Existing script to register, relative to the current directory, instead of one in `.cmd/scripts`.
+    path: Option<PathBuf>,
+    /// Register the script without creating its file or opening the editor.
+    no_stub: bool,
+    /// File, or name of one in `.cmd/templates`, the script is created from instead of the stub.
+    template: Option<String>,
+    /// Values of the `{{KEY}}` placeholders of the template besides `alias`, `description`, and `date`.
+    template_vars: Vec<(String, String)>,
+    /// Fail instead of keeping placeholders without a value.
+    strict: bool,
+}
+
+impl AddOptions {
+    fn new(matched_args: &ArgMatches) -> AddOptions {
+        AddOptions{
+            arg_spec: matched_args.get_many::<String>("arg-spec").unwrap_or_default().cloned().collect(),
+            group: matched_args.get_one::<String>("group").cloned(),
+            depends: matched_args.get_many::<String>("depends").unwrap_or_default().cloned().collect(),
+            readonly: matched_args.get_flag("readonly"),
+            tags: matched_args.get_many::<String>("tag").unwrap_or_default().cloned().collect(),
+            mode: matched_args.get_one::<u32>("chmod").copied(),
+            usage: matched_args.get_one::<String>("usage").cloned(),
+            default_args: matched_args.get_one::<Vec<String>>("default-args").cloned().unwrap_or_default(),
+            path: matched_args.get_one::<PathBuf>("path").cloned(),
+            no_stub: matched_args.get_flag("no-stub"),
+            template: matched_args.get_one::<String>("template").cloned(),
+            template_vars: matched_args.get_many::<(String, String)>("template-var").unwrap_or_default().cloned().collect(),
+            strict: matched_args.get_flag("strict"),
         }
-    } else {
-        let commands_file = ensure_initialized(&scope.path, false);
-        edit_file(&commands_file);
-    }
-}
-
-fn edit_file(script_path: &PathBuf) {
-    let editor = std::env::var("EDITOR").unwrap_or("vim".into());
-    let f: String = path_to_str(script_path);
-    execute(&editor, [f]);
-}
-
-fn cmd_remove(alias: &String, groups: &mut Vec<CmdGroup>) {
-    if let Some(command) = find_command(&alias, &groups) {
-        for group in groups {
-            if group.scope == command.scope {
-                let osz = group.commands.len();
-                let mut res = vec![];
-                std::mem::swap(&mut res, &mut group.commands);
-                res = res.into_iter().filter(|c|{
-                    c.alias != *command.alias
-                }).collect();
-                group.commands = res;
-                let sz = group.commands.len();
-                if sz != osz {
-                    let path = group.scope.path.join(".cmd").join("index.json").to_owned();
-                    save_to_file(&path, &group);
-                    return;
-                }
-            }
+    }
+}
+
+fn script_stub(shell: &str) -> String {
+    format!("#!/usr/bin/env {shell}\n\necho \"Hello world\"\n")
+}
+
+/// Replaces the `{{KEY}}` placeholders by their values, unknown ones are kept unless `strict`.
+fn render_template(template: &str, vars: &[(String, String)], strict: bool) -> Result<String> {
+    let mut rendered = String::new();
+    let mut unknown = vec![];
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}").map(|e| start + e) else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+        let key = rest[start + 2..end].trim();
+        match vars.iter().rev().find(|(k, _)| k == key) {
+            Some((_, value)) => rendered.push_str(value),
+            None => {
+                rendered.push_str(&rest[start..end + 2]);
+                unknown.push(key.to_string());
+            },
         }
-    } else {
+        rest = &rest[end + 2..];
+    }
+    rendered.push_str(rest);
+    if strict && !unknown.is_empty() {
+        anyhow::bail!("the template has placeholders without a value: {}", unknown.join(", "));
+    }
+    Ok(rendered)
+}
+
+/// Content of the new script from the template of the options, the stub if there is none.
+fn script_content(alias: &String, description: &String, options: &AddOptions, scope: &Scope) -> String {
+    let Some(template) = &options.template else {
+        return script_stub("sh");
+    };
+    let named = scope.path.join(".cmd").join("templates").join(template);
+    let path = if named.is_file() { named } else { PathBuf::from(template) };
+    let data = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("unable to read template {}: {e}", path_to_str(&path)));
+    let date = list::format_time(SystemTime::now());
+    let mut vars = vec![
+        ("alias".to_string(), alias.to_owned()),
+        ("description".to_string(), description.to_owned()),
+        ("date".to_string(), date.split_once(' ').map_or(date.as_str(), |(day, _)| day).to_string()),
+    ];
+    vars.extend(options.template_vars.iter().cloned());
+    render_template(&data, &vars, options.strict).unwrap_or_else(|e| {
+        println!("ERR: unable to create {alias}, {e}");
+        std::process::exit(1);
+    })
+}
+
+fn cmd_add(alias: &String, description: &String, options: &AddOptions, scope: &Scope, groups: &mut Vec<CmdGroup>) {
+    let content = script_content(alias, description, options, scope);
+    let command = add_command(alias, description, &content, options, scope, groups);
+    finish_add(&command, !options.no_stub);
+}
+
+fn finish_add(command: &Cmd, edit: bool) {
+    if edit && !edit_file(&command.abs_path) {
+        let alias = &command.alias;
+        let rel_path = &command.rel_path;
+        println!("INFO: {alias} was added with script {rel_path}, edit it later with `edit {alias}`");
+        std::process::exit(1);
+    }
+    if command.readonly {
+        set_owner_write(&command.abs_path, false);
+    }
+}
+
+/// Downloads the script body with `curl`, or `wget` where curl is missing.
+fn download(url: &str) -> Result<String> {
+    let attempts: [(&str, &[&str]); 2] = [("curl", &["-fsSL", url]), ("wget", &["-qO-", url])];
+    for (program, args) in attempts {
+        match std::process::Command::new(program).args(args).stderr(Stdio::inherit()).output() {
+            Ok(output) if output.status.success() => return Ok(String::from_utf8(output.stdout)?),
+            Ok(output) => anyhow::bail!("{program} failed with {}", output.status),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => anyhow::bail!("unable to run {program}: {e}"),
+        }
+    }
+    anyhow::bail!("neither curl nor wget is available")
+}
+
+/// Adds a script with the downloaded content, remote code is saved only once the user passes `trust`.
+fn cmd_add_from_url(alias: &String, description: &String, url: &str, trust: bool, options: &AddOptions, scope: &Scope, groups: &mut Vec<CmdGroup>) {
+    let content = download(url).unwrap_or_else(|e| {
+        println!("ERR: unable to download {url}: {e}");
+        std::process::exit(1);
+    });
+    if !trust {
+        println!("INFO: beginning of the script from {url}:");
+        content.lines().take(10).for_each(|line| println!("    {line}"));
+        println!("ERR: review the script and run again with --trust to add it");
+        std::process::exit(1);
+    }
+    let command = add_command(alias, description, &content, options, scope, groups);
+    if std::fs::read_to_string(&command.abs_path).ok().as_deref() != Some(content.as_str()) {
+        println!("WARN: kept the existing script {}, the download was not written into it", command.rel_path);
+    }
+    finish_add(&command, false);
+}
+
+/// Asks for the alias and the other fields on the terminal, values given on the command line are offered as defaults.
+fn cmd_add_interactive(alias: Option<&String>, description: Option<&String>, options: &AddOptions, scope: &Scope, groups: &mut Vec<CmdGroup>) {
+    if !std::io::stdin().is_terminal() {
+        println!("ERR: --interactive needs a terminal, pass the ALIAS and options instead");
+        std::process::exit(1);
+    }
+    let ask = |prompt: &str, initial: &str| prompt_with_initial(prompt, initial).unwrap_or_else(|| {
+        println!("INFO: nothing was added");
+        std::process::exit(1);
+    });
+    let mut initial = alias.cloned().unwrap_or_default();
+    let alias = loop {
+        let answer = ask("alias: ", &std::mem::take(&mut initial));
+        if answer.is_empty() {
+            println!("the alias must not be empty");
+        } else if is_builtin(&answer) {
+            println!("{answer} is a builtin command, choose another alias");
+        } else if find_command(&answer, groups).is_some() {
+            println!("{answer} already exists, choose another alias");
+        } else {
+            break answer;
+        }
+    };
+    let description = ask("description: ", description.map(String::as_str).unwrap_or_default());
+    let tags = ask("tags (comma separated): ", &options.tags.join(","));
+    let shell = ask("shell: ", "sh");
+    let edit = ask("open the editor? [Y/n] ", "");
+    let mut options = AddOptions{
+        tags: tags.split(',').map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect(),
+        ..options.clone()
+    };
+    options.tags.dedup();
+    let shell = if shell.is_empty() { "sh".to_string() } else { shell };
+    let command = add_command(&alias, &description, &script_stub(&shell), &options, scope, groups);
+    finish_add(&command, !edit.eq_ignore_ascii_case("n") && !edit.eq_ignore_ascii_case("no"));
+}
+
+fn set_owner_write(path: &Path, writable: bool) {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        println!("WARN: unable to change permissions of missing {}", path_to_str(&path.to_path_buf()));
+        return;
+    };
+    let mode = metadata.permissions().mode();
+    let mode = if writable { mode | 0o200 } else { mode & !0o200 };
+    if let Err(e) = std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)) {
+        println!("WARN: unable to change permissions of {}: {e}", path_to_str(&path.to_path_buf()));
+    }
+}
+
+/// Opens the scripts in the editor, the readonly ones are made writable only for the time of editing.
+fn edit_scripts(commands: &[&Cmd]) -> bool {
+    let locked: Vec<&&Cmd> = commands.iter().filter(|c| c.readonly).collect();
+    locked.iter().for_each(|c| set_owner_write(&c.abs_path, true));
+    let paths: Vec<PathBuf> = commands.iter().map(|c| c.abs_path.to_owned()).collect();
+    let result = edit_files(&paths);
+    locked.iter().for_each(|c| set_owner_write(&c.abs_path, false));
+    result
+}
+
+/// Sets or clears the readonly flag of a script together with the owner-write bit of its file.
+fn cmd_lock(alias: &String, readonly: bool, groups: &mut [CmdGroup]) {
+    let Some(command) = find_command(alias, groups) else {
         println!("{alias} is an unknown command");
+        return;
+    };
+    let group = groups.iter_mut().find(|g| g.scope == command.scope).expect("group of a found command");
+    for entry in group.commands.iter_mut().filter(|c| c.alias == *alias) {
+        entry.readonly = readonly;
     }
+    save_to_file(&index_path(&group.scope.path), group);
+    set_owner_write(&command.abs_path, !readonly);
 }
 
-fn get_group_mut<'a>(scope_type: &ScopeKind, groups: &'a mut Vec<CmdGroup>) -> Option<&'a mut CmdGroup> {
-    for group in groups {
-        if group.scope.kind == *scope_type {
-            return Some(group);
+/// Script files get a shorter name, so that the alias does not hit limits of the filesystem.
+const MAX_FILE_STEM_LENGTH: usize = 64;
+
+fn file_stem(alias: &str) -> String {
+    let mut stem: String = alias.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "-_.".contains(c) { c } else { '_' })
+        .take(MAX_FILE_STEM_LENGTH)
+        .collect();
+    if stem.starts_with('.') {
+        stem.replace_range(..1, "_");
+    }
+    stem
+}
+
+/// Resolves the `.` and `..` components of the path without looking at the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {},
+            std::path::Component::ParentDir => { normalized.pop(); },
+            _ => normalized.push(component),
         }
     }
-    None
+    normalized
 }
 
-fn find_command(alias: &String, groups: &Vec<CmdGroup>) -> Option<Cmd> {
-    for group in groups {
-        for command in &group.commands {
-            if command.alias == *alias {
-                return Some(command.to_owned());
-            }
+/// The path given relative to the current directory as relative to the scope, if it lies within it.
+fn scope_rel_path(path: &Path, scope: &Scope) -> String {
+    let current_dir = std::env::current_dir().expect("unable to retrieve current directory");
+    let normalized = normalize(&current_dir.join(path));
+    match normalized.strip_prefix(&scope.path) {
+        Ok(rel_path) => format!("./{}", path_to_str(&rel_path.to_path_buf())),
+        Err(_) => path_to_str(&normalized),
+    }
+}
+
+/// Path of the script of a new alias; characters unsafe in file names are replaced, e.g. `db/migrate` is
+/// stored in `db_migrate.sh`, and a number is appended if another command already uses that file.
+fn script_rel_path(alias: &str, group: &CmdGroup) -> String {
+    let stem = file_stem(alias);
+    let taken = |path: &String| group.commands.iter().any(|c| c.rel_path == *path);
+    let mut rel_path = format!("./.cmd/scripts/{stem}.sh");
+    let mut number = 2;
+    while taken(&rel_path) {
+        rel_path = format!("./.cmd/scripts/{stem}-{number}.sh");
+        number += 1;
+    }
+    rel_path
+}
+
+/// Registers a new command in the scope, its script is created with the given content unless it already exists.
+fn add_command(alias: &String, description: &String, content: &str, options: &AddOptions, scope: &Scope, groups: &mut Vec<CmdGroup>) -> Cmd {
+    if is_builtin(alias) {
+        panic!("unable to create {alias} because it is a builtin command");
+    }
+    if find_command(alias, groups).is_some() {
+        panic!("unable to create {alias} because it already exists");
+    }
+    if let Some(problem) = validate::alias_problem(alias) {
+        panic!("unable to create {alias} because {problem}");
+    }
+    if let Some(dependency) = options.depends.iter().find(|d| find_command(d, groups).is_none()) {
+        panic!("unable to create {alias} because its dependency {dependency} is an unknown command");
+    }
+    let commands_file = ensure_initialized(&scope.path, false);
+    if get_group_mut(&scope.kind, groups).is_none() {
+        groups.push(CmdGroup{commands: vec![], scope: scope.to_owned()});
+    }
+    let group = get_group_mut(&scope.kind, groups).expect("group was just ensured");
+    let rel_path = match &options.path {
+        Some(path) => scope_rel_path(path, &group.scope),
+        None => script_rel_path(alias, group),
+    };
+    let mut command = Cmd::new(alias, &rel_path, description, &group.scope);
+    command.arg_spec = options.arg_spec.to_owned();
+    command.group = options.group.to_owned();
+    command.depends = options.depends.to_owned();
+    command.readonly = options.readonly;
+    command.tags = options.tags.to_owned();
+    command.usage = options.usage.to_owned();
+    command.default_args = options.default_args.to_owned();
+    if options.no_stub {
+        if !command.abs_path.exists() {
+            println!("WARN: {} does not exist, running {alias} fails until it is created", command.rel_path);
         }
+    } else if !command.abs_path.exists() {
+        let mut file = std::fs::File::create(&command.abs_path).expect("unable to create file");
+        file.write_all(content.as_bytes()).expect("unable to write into file");
+        std::fs::set_permissions(&command.abs_path, std::fs::Permissions::from_mode(options.mode.unwrap_or(0o775))).expect("unable to assign script permissions");
     }
-    None
+    group.commands.push(command.to_owned());
+    save_to_file(&commands_file, group);
+    command
 }
 
-fn main() {
-    let mut builder = command!()
-        .disable_help_flag(true)
-        .disable_help_subcommand(true)
-        .disable_version_flag(true)
-        .subcommand_required(true)
-        .arg_required_else_help(true)
-        .subcommands([
-            Command::new("--init").visible_alias("-i")
-            .about("Setup local scope in the current directory"),
-            Command::new("--add").visible_alias("-a")
-            .arg(arg!(<ALIAS>).value_hint(ValueHint::Other))
-            .arg(arg!([DESCRIPTION]))
-            .about("Create script and open it in the $EDITOR"),
-            Command::new("--edit").visible_alias("-e")
-            .arg(arg!([ALIAS]).value_parser(clap::value_parser!(String)))
-            .about("Open script index or [SCRIPT] in the $EDITOR"),
-            Command::new("--remove").visible_alias("-r")
-            .arg(arg!(<ALIAS>).value_hint(ValueHint::Other))
-            .about("Remove script from the index (does NOT remove file)"),
-            Command::new("--version")
-            .about("Prints out version information")
-        ])
-        .args([
-            arg!(-l --local "Force local scope"),
-            arg!(-g --global "Force global scope"),
-        ].map(|x|x.required(false)))
-        ;
-    let mut cmd_groups: Vec<CmdGroup> = vec![];
-    let global_scope = Scope{kind: ScopeKind::GLOBAL, path: find_global_dir()};
-    if let Ok(global) = CmdGroup::new(&global_scope) {
-        cmd_groups.push(global.to_owned());
+/// Runs an interactive $SHELL with its history redirected to a temporary file
+/// and turns the commands typed there into a new script.
+fn cmd_record(alias: &String, description: &String, scope: &Scope, groups: &mut Vec<CmdGroup>) {
+    if is_builtin(alias) || find_command(alias, groups).is_some() {
+        panic!("unable to record {alias} because it already exists");
+    }
+    let shell = std::env::var("SHELL").unwrap_or("sh".into());
+    let history_path = std::env::temp_dir().join(format!("cmd-record-{}", std::process::id()));
+    std::fs::write(&history_path, "").expect("unable to create the history file");
+    println!("INFO: recording {alias}, exit the shell to finish");
+    let status = std::process::Command::new(&shell)
+        .arg("-i")
+        .env("HISTFILE", &history_path)
+        .env("SAVEHIST", "10000")
+        .status();
+    let history = std::fs::read_to_string(&history_path).unwrap_or_default();
+    let _ = std::fs::remove_file(&history_path);
+    if let Err(e) = status {
+        panic!("unable to launch shell {shell}: {e}");
+    }
+    let lines: Vec<&str> = history.lines()
+        .map(history_line)
+        .filter(|l| !l.is_empty() && *l != "exit")
+        .collect();
+    if lines.is_empty() {
+        println!("INFO: no commands were recorded, {alias} was not created");
+        return;
+    }
+    let shell_name = Path::new(&shell).file_name().and_then(|n| n.to_str()).unwrap_or("sh");
+    let content = format!("#!/usr/bin/env {shell_name}\n\n{}\n", lines.join("\n"));
+    let command = add_command(alias, description, &content, &AddOptions::default(), scope, groups);
+    println!("INFO: recorded {} command(s) into {}", lines.len(), command.rel_path);
+}
+
+/// Parses a bash `alias name='command'` line into the name and the command.
+fn parse_alias_line(line: &str) -> Option<(String, String)> {
+    let (name, value) = line.strip_prefix("alias ")?.trim().split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return None;
     }
-    let mut local_commands: Option<CmdGroup> = None;
-    let local_scope = match find_local_dir() {
-        Some(local_dir) => Some(Scope{kind: ScopeKind::LOCAL, path: local_dir}),
-        None => None,
+    let value = value.trim();
+    let command = if let Some(quoted) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        quoted.replace("'\\''", "'")
+    } else if let Some(quoted) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        quoted.replace("\\\"", "\"")
+    } else if !value.is_empty() && !value.contains(char::is_whitespace) {
+        value.to_string()
+    } else {
+        return None;
     };
-    if let Some(scope) = &local_scope {
-        match CmdGroup::new(&scope) {
-            Ok(commands) => local_commands = Some(commands),
-            Err(e) => println!("ERR: {:?}", e),
+    Some((name.to_string(), command))
+}
+
+/// Imports all aliases or none: the scripts are staged in a temporary folder, moved into place together,
+/// and the index is written once at the end; any failure moves them back and leaves the scope as it was.
+fn cmd_import_aliases(file: &PathBuf, scope: &Scope, groups: &mut Vec<CmdGroup>) {
+    let data = std::fs::read_to_string(file).unwrap_or_else(|e| panic!("unable to read {}: {e}", path_to_str(file)));
+    let commands_file = ensure_initialized(&scope.path, false);
+    let mut group = groups.iter().find(|g| g.scope.kind == scope.kind).cloned()
+        .unwrap_or(CmdGroup{commands: vec![], scope: scope.to_owned()});
+    let mut imported: Vec<(Cmd, String)> = vec![];
+    for (i, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
         }
+        let Some((alias, command)) = parse_alias_line(line) else {
+            println!("WARN: skipping line {} which is not an alias definition: {line}", i + 1);
+            continue;
+        };
+        let duplicate = imported.iter().any(|(c, _)| c.alias == alias);
+        if is_builtin(&alias) || find_command(&alias, groups).is_some() || duplicate {
+            println!("WARN: skipping {alias} because it already exists");
+            continue;
+        }
+        if let Some(problem) = validate::alias_problem(&alias) {
+            println!("WARN: skipping {alias} because {problem}");
+            continue;
+        }
+        let rel_path = script_rel_path(&alias, &group);
+        let entry = Cmd::new(&alias, &rel_path, &command, &group.scope);
+        group.commands.push(entry.to_owned());
+        imported.push((entry, format!("#!/usr/bin/env sh\n\n{command} \"$@\"\n")));
     }
-    if let Some(local_commands) = &local_commands {
-        cmd_groups.push(local_commands.to_owned());
+    let staging = scope.path.join(".cmd").join("import.tmp");
+    if let Err(e) = import_scripts(&imported, &staging, &commands_file, &group) {
+        let _ = std::fs::remove_dir_all(&staging);
+        panic!("unable to import the aliases, nothing was changed: {e}");
     }
-    for group in &cmd_groups {
-        for command in &group.commands {
-            builder = builder.subcommand(
-                Command::new(&command.alias)
-                .about(&command.description)
-                .arg(arg!([args]...))
-                );
+    let _ = std::fs::remove_dir_all(&staging);
+    match groups.iter_mut().find(|g| g.scope.kind == scope.kind) {
+        Some(existing) => *existing = group,
+        None => groups.push(group),
+    }
+    println!("INFO: imported {} alias(es)", imported.len());
+}
+
+fn import_scripts(imported: &[(Cmd, String)], staging: &Path, commands_file: &Path, group: &CmdGroup) -> Result<()> {
+    let _ = std::fs::remove_dir_all(staging);
+    std::fs::create_dir(staging)?;
+    let mut staged = vec![];
+    for (i, (command, content)) in imported.iter().enumerate() {
+        if command.abs_path.exists() {
+            println!("WARN: keeping the existing script {} of {}", command.rel_path, command.alias);
+            continue;
         }
+        let path = staging.join(i.to_string());
+        std::fs::write(&path, content)?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o775))?;
+        staged.push((path, command.abs_path.to_owned()));
     }
-    let cli_args = builder.get_matches_mut();
-    let (subcommand, matched_args) = match cli_args.subcommand() {
-        Some((subcommand, matched_args)) => (subcommand, matched_args),
-        None => return,
-    };
-    match subcommand {
-        "--init"|"-i" => {
-            cmd_init_local();
-        },
-        "--add"|"-a" => {
-            let alias: &String = matched_args.get_one::<String>("ALIAS").unwrap();
-            let empty = "".to_string();
-            let description: &String = matched_args.get_one::<String>("DESCRIPTION").unwrap_or(&empty);
-            let scope = choose_scope(&cli_args, global_scope, local_scope);
-            cmd_add(&alias, &description, &scope, &mut cmd_groups);
-        },
-        "--edit"|"-e" => {
-            let some_alias = matched_args.get_one::<String>("ALIAS");
-            let scope = choose_scope(&cli_args, global_scope, local_scope);
-            cmd_edit(some_alias, &scope, &cmd_groups);
-        },
-        "--remove"|"-r" => {
-            let alias = matched_args.get_one::<String>("ALIAS").unwrap();
-            cmd_remove(&alias, &mut cmd_groups);
-        },
-        "--version" => {
-            print!("{}", builder.render_version());
-        },
-        _ => {
-            let args = match matched_args.get_many::<String>("args") {
-                Some(s) => s.into_iter().map(|s|s.to_string()).collect(),
-                None => vec![],
-            };
-            if let Some(command) = find_command(&(*subcommand).into(), &cmd_groups) {
-                if command.scope.path.join(&command.rel_path).exists() {
-                    let command_path = command.scope.path.join(&command.rel_path);
-                    let command = command_path.into_os_string().into_string().expect("cannot convert path to string");
-                    execute(&command, args);
-                } else {
-                    let alias = &command.alias;
-                    let path_str = &command.rel_path;
-                    println!("the {alias} alias is pointed to a non-existant file {path_str}");
-                }
-            } else {
-                panic!("unknown subcommand returned from parser");
-            }
-        },
+    let mut moved = vec![];
+    for (from, to) in staged {
+        if let Err(e) = std::fs::rename(&from, &to) {
+            undo_renames(&moved);
+            return Err(e.into());
+        }
+        moved.push((from, to));
+    }
+    if let Err(e) = try_save_to_file(commands_file, group) {
+        undo_renames(&moved);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Strips the `: <timestamp>:<duration>;` prefix of zsh extended history.
+fn history_line(line: &str) -> &str {
+    if let Some(rest) = line.strip_prefix(": ") {
+        if let Some((_, command)) = rest.split_once(';') {
+            return command.trim();
+        }
+    }
+    line.trim()
+}
+
+fn is_builtin(alias: &str) -> bool {
+    build_cli().get_subcommands().any(|c| c.get_name() == alias || c.get_all_aliases().any(|a| a == alias))
+}
+
+fn cmd_edit(some_alias: Option<&String>, all: bool, force: bool, scope: &Scope, cmd_groups: &[CmdGroup]) {
+    if all {
+        let mut commands = vec![];
+        for command in cmd_groups.iter().filter(|g| g.scope == *scope).flat_map(|g| &g.commands) {
+            if !command.abs_path.exists() {
+                println!("WARN: skipping {}, its script {} does not exist", command.alias, command.rel_path);
+            } else if command.readonly && !force {
+                println!("WARN: skipping {}, it is readonly, use --force to edit it too", command.alias);
+            } else {
+                commands.push(command);
+            }
+        }
+        if commands.is_empty() {
+            println!("INFO: there are no scripts to edit in the {} scope", scope.kind.name());
+        } else if !edit_scripts(&commands) {
+            std::process::exit(1);
+        }
+    } else if let Some(alias) = some_alias{
+        if let Some(command) = find_command(alias, cmd_groups) {
+            if command.readonly && !force {
+                println!("ERR: {alias} is readonly, use --force to edit it anyway or `unlock {alias}`");
+                std::process::exit(1);
+            }
+            if !edit_scripts(&[&command]) {
+                std::process::exit(1);
+            }
+        } else {
+            println!("{alias} is an unknown command");
+        }
+    } else {
+        let commands_file = ensure_initialized(&scope.path, false);
+        if !edit_file(&commands_file) {
+            std::process::exit(1);
+        }
+    }
+}
+
+fn edit_file(script_path: &PathBuf) -> bool {
+    edit_files(std::slice::from_ref(script_path))
+}
+
+/// Opens the files in a single $EDITOR session, returns false and reports the reason if the editor cannot be launched.
+fn edit_files(paths: &[PathBuf]) -> bool {
+    let editor = config::load().editor.to_owned().or(std::env::var("EDITOR").ok()).unwrap_or("vim".into());
+    let files: Vec<String> = paths.iter().map(path_to_str).collect();
+    match execute(&editor, files, &[], None) {
+        Ok(_) => true,
+        Err(e) => {
+            let reason = match e.kind() {
+                std::io::ErrorKind::NotFound => "not found".to_string(),
+                _ => e.to_string(),
+            };
+            eprintln!("error: could not launch editor '{editor}' ($EDITOR): {reason}");
+            false
+        },
+    }
+}
+
+/// Aliases of the commands which depend on the given one.
+fn dependents_of(alias: &String, groups: &[CmdGroup]) -> Vec<String> {
+    groups.iter()
+        .flat_map(|g| &g.commands)
+        .filter(|c| c.depends.contains(alias))
+        .map(|c| c.alias.to_owned())
+        .collect()
+}
+
+/// Script file which `remove --purge` deletes, `None` if it has to be kept.
+fn purged_file(command: &Cmd, groups: &[CmdGroup]) -> Option<PathBuf> {
+    let path = &command.abs_path;
+    if !path.exists() {
+        println!("INFO: {} does not exist, there is no file to delete", command.rel_path);
+        return None;
+    }
+    let cmd_dir = std::fs::canonicalize(command.scope.path.join(".cmd")).ok()?;
+    if !std::fs::canonicalize(path).is_ok_and(|p| p.starts_with(&cmd_dir)) {
+        println!("INFO: keeping {} as it is not within .cmd", command.rel_path);
+        return None;
+    }
+    let shared = groups.iter().flat_map(|g| &g.commands)
+        .any(|c| c.alias != command.alias && c.abs_path == *path);
+    if shared {
+        println!("INFO: keeping {} as another script uses it", command.rel_path);
+        return None;
+    }
+    Some(path.to_owned())
+}
+
+/// Removes the script from the index, with `purge` also its file; `dry_run` only reports what would be done.
+fn cmd_remove(alias: &String, force: bool, purge: bool, dry_run: bool, groups: &mut [CmdGroup]) -> bool {
+    let Some(command) = find_command(alias, groups) else {
+        println!("{alias} is an unknown command");
+        return false;
+    };
+    let dependents = dependents_of(alias, groups);
+    if !dependents.is_empty() {
+        if !force {
+            println!("ERR: {alias} is a dependency of {}, use --force to remove it anyway", dependents.join(", "));
+            std::process::exit(1);
+        }
+        println!("WARN: {} depend on the removed {alias}", dependents.join(", "));
+    }
+    let file = if purge { purged_file(&command, groups) } else { None };
+    let index = index_path(&command.scope.path);
+    if dry_run {
+        println!("would remove: {alias} from {}", path_to_str(&index));
+        if let Some(file) = &file {
+            println!("would delete: {}", path_to_str(file));
+        }
+        return false;
+    }
+    let Some(group) = groups.iter_mut().find(|g| g.scope == command.scope) else {
+        return false;
+    };
+    group.commands.retain(|c| c.alias != command.alias);
+    save_to_file(&index, group);
+    if let Some(file) = &file {
+        if let Err(e) = std::fs::remove_file(file) {
+            println!("WARN: unable to delete {}: {e}", path_to_str(file));
+        }
+    }
+    true
+}
+
+/// Changes the alias of a command and of all references to it in dependencies, the file stays.
+fn cmd_rename(alias: &String, new_alias: &String, groups: &mut [CmdGroup]) -> bool {
+    if find_command(alias, groups).is_none() {
+        println!("{alias} is an unknown command");
+        return false;
+    }
+    if is_builtin(new_alias) {
+        panic!("unable to rename {alias} to {new_alias} because it is a builtin command");
+    }
+    if find_command(new_alias, groups).is_some() {
+        panic!("unable to rename {alias} to {new_alias} because it already exists");
+    }
+    for group in groups.iter_mut() {
+        let mut changed = false;
+        for command in &mut group.commands {
+            if command.alias == *alias {
+                command.alias = new_alias.to_owned();
+                changed = true;
+            }
+            for dependency in command.depends.iter_mut().filter(|d| *d == alias) {
+                *dependency = new_alias.to_owned();
+                changed = true;
+            }
+        }
+        if changed {
+            save_to_file(&index_path(&group.scope.path), group);
+        }
+    }
+    true
+}
+
+/// Sets the description, without a new text it is edited in place on the terminal.
+fn cmd_describe(alias: &String, text: Option<&String>, groups: &mut [CmdGroup]) {
+    let Some(command) = find_command(alias, groups) else {
+        println!("{alias} is an unknown command");
+        return;
+    };
+    let description = match text {
+        Some(text) => text.to_owned(),
+        None if std::io::stdin().is_terminal() => {
+            match prompt_with_initial("description: ", &command.description) {
+                Some(description) => description,
+                None => return,
+            }
+        },
+        None => {
+            if !edit_file(&index_path(&command.scope.path)) {
+                std::process::exit(1);
+            }
+            return;
+        },
+    };
+    let group = groups.iter_mut().find(|g| g.scope == command.scope).expect("group of a found command");
+    for entry in group.commands.iter_mut().filter(|c| c.alias == *alias) {
+        entry.description = description.to_owned();
+    }
+    save_to_file(&index_path(&group.scope.path), group);
+}
+
+/// Reads a line which starts pre-filled with the initial text, `None` if the user cancels it.
+fn prompt_with_initial(prompt: &str, initial: &str) -> Option<String> {
+    let mut editor = rustyline::DefaultEditor::new().expect("unable to set up the line editor");
+    match editor.readline_with_initial(prompt, (initial, "")) {
+        Ok(line) => Some(line.trim().to_string()),
+        Err(rustyline::error::ReadlineError::Interrupted | rustyline::error::ReadlineError::Eof) => None,
+        Err(e) => panic!("unable to read the line: {e}"),
+    }
+}
+
+fn cmd_depends(alias: &String, dependencies: Vec<String>, clear: bool, groups: &mut [CmdGroup]) {
+    let Some(command) = find_command(alias, groups) else {
+        println!("{alias} is an unknown command");
+        return;
+    };
+    if dependencies.is_empty() && !clear {
+        command.depends.iter().for_each(|d| println!("{d}"));
+        return;
+    }
+    for dependency in &dependencies {
+        if find_command(dependency, groups).is_none() {
+            panic!("unable to depend on {dependency} because it is an unknown command");
+        }
+    }
+    let group = groups.iter_mut().find(|g| g.scope == command.scope).expect("group of a found command");
+    for entry in group.commands.iter_mut().filter(|c| c.alias == *alias) {
+        entry.depends = dependencies.to_owned();
+    }
+    let updated = find_command(alias, groups).expect("command was just updated");
+    if let Err(e) = resolve_dependencies(&updated, groups) {
+        panic!("unable to set the dependencies of {alias}: {e}");
+    }
+    let group = groups.iter().find(|g| g.scope == command.scope).expect("group of a found command");
+    save_to_file(&index_path(&group.scope.path), group);
+}
+
+/// Dependencies of the command in the order they should run, each one only once.
+fn resolve_dependencies(command: &Cmd, groups: &[CmdGroup]) -> Result<Vec<Cmd>> {
+    fn visit(command: &Cmd, groups: &[CmdGroup], stack: &mut Vec<String>, order: &mut Vec<Cmd>) -> Result<()> {
+        if order.iter().any(|c| c.alias == command.alias) {
+            return Ok(());
+        }
+        if stack.contains(&command.alias) {
+            stack.push(command.alias.to_owned());
+            anyhow::bail!("dependency cycle {}", stack.join(" -> "));
+        }
+        stack.push(command.alias.to_owned());
+        for dependency in &command.depends {
+            let Some(dependency) = find_command(dependency, groups) else {
+                anyhow::bail!("{} depends on {dependency} which is an unknown command", command.alias);
+            };
+            visit(&dependency, groups, stack, order)?;
+        }
+        stack.pop();
+        order.push(command.to_owned());
+        Ok(())
+    }
+    let mut order = vec![];
+    visit(command, groups, &mut vec![], &mut order)?;
+    order.pop();
+    Ok(order)
+}
+
+/// Reads the variables passed to the script by `--env-file` and `--env`.
+fn run_envs(cli_args: &ArgMatches) -> Vec<(String, String)> {
+    let mut envs = vec![];
+    if let Some(path) = cli_args.get_one::<PathBuf>("env-file") {
+        let data = std::fs::read_to_string(path).unwrap_or_else(|e| panic!("unable to read env file {}: {e}", path_to_str(path)));
+        envs = parse_env_file(&data).unwrap_or_else(|e| panic!("invalid env file {}: {e}", path_to_str(path)));
+    }
+    if let Some(pairs) = cli_args.get_many::<(String, String)>("env") {
+        envs.extend(pairs.cloned());
+    }
+    envs
+}
+
+/// Parses dotenv-style `KEY=value` lines; `#` comments, `export` prefixes, and quoted values are allowed.
+fn parse_env_file(data: &str) -> Result<Vec<(String, String)>> {
+    let mut envs = vec![];
+    for (number, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            anyhow::bail!("line {} is not of the form KEY=value", number + 1);
+        };
+        let value = value.trim();
+        let value = if let Some(quoted) = value.strip_prefix('"') {
+            let Some(end) = quoted.rfind('"') else {
+                anyhow::bail!("line {} has an unterminated quote", number + 1);
+            };
+            unescape(&quoted[..end])
+        } else if let Some(quoted) = value.strip_prefix('\'') {
+            let Some(end) = quoted.rfind('\'') else {
+                anyhow::bail!("line {} has an unterminated quote", number + 1);
+            };
+            quoted[..end].to_string()
+        } else {
+            value.split(" #").next().unwrap_or_default().trim_end().to_string()
+        };
+        envs.push((key.trim().to_string(), value));
+    }
+    Ok(envs)
+}
+
+/// Resolves `\n`, `\"`, and `\\` of a double-quoted value, other backslashes are kept.
+fn unescape(value: &str) -> String {
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => { result.push('\n'); chars.next(); },
+            ('\\', Some(escaped @ ('"' | '\\'))) => { result.push(escaped); chars.next(); },
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// Splits the text into words like a shell, single and double quotes group words with spaces.
+fn split_words(text: &str) -> std::result::Result<Vec<String>, String> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+    for c in text.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => word.get_or_insert_default().push(c),
+            None if c == '\'' || c == '"' => {
+                quote = Some(c);
+                word.get_or_insert_default();
+            },
+            None if c.is_whitespace() => words.extend(word.take()),
+            None => word.get_or_insert_default().push(c),
+        }
+    }
+    if let Some(q) = quote {
+        return Err(format!("unterminated {q} quote"));
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+fn parse_env_pair(pair: &str) -> std::result::Result<(String, String), String> {
+    match pair.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err("expected KEY=VALUE".to_string()),
+    }
+}
+
+/// Runs the dependencies of the command first and the command only if all of them succeed. With
+/// `--capture-json` a single json line is printed, the results of the dependencies are nested in it.
+fn run_with_dependencies(command: &Cmd, args: Vec<String>, options: &RunOptions, groups: &[CmdGroup]) -> ExitStatus {
+    let dependencies = resolve_dependencies(command, groups).unwrap_or_else(|e| panic!("unable to run {}: {e}", command.alias));
+    let mut dependency_results = vec![];
+    for dependency in dependencies {
+        if dependency.abs_path.exists() {
+            record_run(&dependency);
+        }
+        let (status, result) = cmd_run(&dependency, vec![], options);
+        dependency_results.extend(result.map(serde_json::Value::Object));
+        if !status.success() {
+            let message = format!("ERR: dependency {} failed, {} is not run", dependency.alias, command.alias);
+            if options.json {
+                eprintln!("{message}");
+                let mut result = JsonResult::new();
+                result.insert("alias".to_string(), command.alias.to_owned().into());
+                result.insert("exit_code".to_string(), status.code().into());
+                result.insert("not_run".to_string(), true.into());
+                print_json(result, dependency_results);
+            } else {
+                println!("{message}");
+            }
+            return status;
+        }
+    }
+    if command.abs_path.exists() {
+        record_run(command);
+    }
+    let (status, result) = cmd_run(command, args, options);
+    if let Some(result) = result {
+        print_json(result, dependency_results);
+    }
+    status
+}
+
+fn print_json(mut result: JsonResult, dependency_results: Vec<serde_json::Value>) {
+    result.insert("dependencies".to_string(), dependency_results.into());
+    println!("{}", serde_json::Value::Object(result));
+}
+
+fn cmd_diff(alias: &String, groups: &[CmdGroup]) {
+    let Some(command) = find_command(alias, groups) else {
+        println!("{alias} is an unknown command");
+        return;
+    };
+    if !is_git_repo(&command.scope.path) {
+        println!("INFO: the {} scope is not inside a git repository, there is nothing to compare {alias} with", command.scope.kind.name());
+        return;
+    }
+    if !run_git(&command.scope.path, &["diff", "--", &path_to_str(&command.abs_path)]) {
+        std::process::exit(1);
+    }
+}
+
+fn cmd_swap(first: &String, second: &String, files: bool, groups: &mut [CmdGroup]) {
+    let Some(a) = find_command(first, groups) else {
+        println!("{first} is an unknown command");
+        return;
+    };
+    let Some(b) = find_command(second, groups) else {
+        println!("{second} is an unknown command");
+        return;
+    };
+    if a.scope != b.scope {
+        panic!("unable to swap {first} and {second} because they are in different scopes");
+    }
+    let mut renamed = vec![];
+    if files {
+        let tmp_path = a.abs_path.with_extension("swap");
+        for (from, to) in [(&a.abs_path, &tmp_path), (&b.abs_path, &a.abs_path), (&tmp_path, &b.abs_path)] {
+            if let Err(e) = std::fs::rename(from, to) {
+                undo_renames(&renamed);
+                panic!("unable to move {} to {}: {e}", path_to_str(from), path_to_str(to));
+            }
+            renamed.push((from.to_owned(), to.to_owned()));
+        }
+    }
+    // the scripts exchange their aliases, so do the dependencies which refer to them
+    let swap_alias = |alias: &mut String| {
+        let other = if *alias == a.alias { &b.alias } else if *alias == b.alias { &a.alias } else { return false };
+        *alias = other.to_owned();
+        true
+    };
+    let group = groups.iter_mut().find(|g| g.scope == a.scope).expect("group of a found command");
+    for command in &mut group.commands {
+        command.depends.iter_mut().for_each(|dependency| { swap_alias(dependency); });
+        let other = if command.alias == a.alias { &b } else if command.alias == b.alias { &a } else { continue };
+        command.alias = other.alias.to_owned();
+        if files {
+            command.rel_path = other.rel_path.to_owned();
+            command.abs_path = other.abs_path.to_owned();
+        }
+    }
+    let path = index_path(&group.scope.path);
+    if let Err(e) = try_save_to_file(&path, group) {
+        undo_renames(&renamed);
+        panic!("unable to save the index file: {e}");
+    }
+    for group in groups.iter_mut().filter(|g| g.scope != a.scope) {
+        let mut changed = false;
+        for command in &mut group.commands {
+            for dependency in command.depends.iter_mut() {
+                changed |= swap_alias(dependency);
+            }
+        }
+        if changed {
+            save_to_file(&index_path(&group.scope.path), group);
+        }
+    }
+}
+
+fn undo_renames(renamed: &[(PathBuf, PathBuf)]) {
+    for (from, to) in renamed.iter().rev() {
+        if let Err(e) = std::fs::rename(to, from) {
+            println!("ERR: unable to move {} back to {}: {e}", path_to_str(to), path_to_str(from));
+        }
+    }
+}
+
+/// Reports scripts which other users could modify, as scriptman would run whatever they put there.
+fn cmd_check_perms(fix: bool, groups: &[CmdGroup]) {
+    let uid = unsafe { libc::getuid() };
+    let mut problems = 0;
+    for command in groups.iter().flat_map(|g| &g.commands) {
+        let Ok(metadata) = std::fs::metadata(&command.abs_path) else {
+            continue;
+        };
+        let path = path_to_str(&command.abs_path);
+        let mode = metadata.permissions().mode();
+        if mode & 0o022 != 0 {
+            if fix {
+                let fixed = std::fs::Permissions::from_mode(mode & !0o022);
+                match std::fs::set_permissions(&command.abs_path, fixed) {
+                    Ok(()) => println!("INFO: removed group and world write permission of {path}"),
+                    Err(e) => {
+                        println!("ERR: unable to change permissions of {path}: {e}");
+                        problems += 1;
+                    },
+                }
+            } else {
+                println!("WARN: {path} is writable by group or others (mode {:o}), fix it by chmod go-w or --fix", mode & 0o777);
+                problems += 1;
+            }
+        }
+        if metadata.uid() != uid {
+            println!("WARN: {path} is owned by another user (uid {}), check its content and chown it", metadata.uid());
+            problems += 1;
+        }
+    }
+    if problems > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Shells whose scripts shellcheck understands.
+const SHELLCHECK_SHELLS: [&str; 4] = ["sh", "bash", "dash", "ksh"];
+
+/// Runs shellcheck over the script, or all scripts, with the shell of their shebang; exits with 1 on any issue.
+fn cmd_lint(some_alias: Option<&String>, groups: &[CmdGroup]) {
+    let commands: Vec<Cmd> = match some_alias {
+        Some(alias) => match find_command(alias, groups) {
+            Some(command) => vec![command],
+            None => {
+                println!("{alias} is an unknown command");
+                std::process::exit(1);
+            },
+        },
+        None => groups.iter().flat_map(|g| g.commands.iter().cloned()).collect(),
+    };
+    let mut failed = vec![];
+    for command in commands {
+        if !command.abs_path.exists() {
+            println!("WARN: {} points to a non-existent file {}", command.alias, command.rel_path);
+            failed.push(command.alias);
+            continue;
+        }
+        let interpreter = script_interpreter(&command.abs_path);
+        let shell = Path::new(&interpreter[0]).file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        if !SHELLCHECK_SHELLS.contains(&shell.as_str()) {
+            println!("INFO: skipping {}, shellcheck does not support {shell} scripts", command.alias);
+            continue;
+        }
+        let path = path_to_str(&command.abs_path);
+        match std::process::Command::new("shellcheck").args(["--shell", &shell, &path]).status() {
+            Ok(status) if status.success() => {},
+            Ok(_) => failed.push(command.alias),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                println!("ERR: shellcheck is not installed, get it from your package manager (e.g. apt install shellcheck) or https://www.shellcheck.net");
+                std::process::exit(1);
+            },
+            Err(e) => panic!("unable to run shellcheck: {e}"),
+        }
+    }
+    if !failed.is_empty() {
+        println!("ERR: issues found in {}", failed.join(", "));
+        std::process::exit(1);
+    }
+}
+
+fn cmd_where(global: &Scope) {
+    let exe = std::env::current_exe().expect("unable to retrieve path of the executable");
+    println!("executable: {}", path_to_str(&exe));
+    println!("global scope: {}", path_to_str(&global.path));
+    if let Some(path) = config::path() {
+        println!("config: {}", path_to_str(&path));
+    }
+}
+
+/// Moves `.cmd` of the scope into the target directory and reports scripts which no longer resolve,
+/// a moved global scope is remembered in the config.
+fn cmd_rename_scope_path(scope: &Scope, target: &Path) {
+    let cmd_dir = scope.path.join(".cmd");
+    if cmd_dir.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink()) {
+        println!("ERR: {} is a symlink, move the directory it points to instead", path_to_str(&cmd_dir));
+        std::process::exit(1);
+    }
+    let group = CmdGroup::new(scope).unwrap_or_else(|e| panic!("unable to load the {} scope: {e}", scope.kind.name()));
+    let current_dir = std::env::current_dir().expect("unable to retrieve current directory");
+    if normalize(&current_dir.join(target)).starts_with(&cmd_dir) {
+        println!("ERR: unable to move {} into itself", path_to_str(&cmd_dir));
+        std::process::exit(1);
+    }
+    std::fs::create_dir_all(target).expect("unable to create the target directory");
+    let target = std::fs::canonicalize(target).expect("unable to resolve the target directory");
+    let new_cmd_dir = target.join(".cmd");
+    if new_cmd_dir.symlink_metadata().is_ok() {
+        println!("ERR: {} already exists", path_to_str(&new_cmd_dir));
+        std::process::exit(1);
+    }
+    if let Err(e) = std::fs::rename(&cmd_dir, &new_cmd_dir) {
+        if e.raw_os_error() != Some(libc::EXDEV) {
+            panic!("unable to move {}: {e}", path_to_str(&cmd_dir));
+        }
+        // renaming does not work across filesystems
+        if let Err(e) = backup::mirror(&cmd_dir, &new_cmd_dir, &|_| false) {
+            let _ = std::fs::remove_dir_all(&new_cmd_dir);
+            panic!("unable to copy {}: {e}", path_to_str(&cmd_dir));
+        }
+        std::fs::remove_dir_all(&cmd_dir).expect("unable to remove the moved scope");
+    }
+    println!("INFO: moved {} to {}", path_to_str(&cmd_dir), path_to_str(&new_cmd_dir));
+    for command in &group.commands {
+        if scope.path.join(&command.rel_path).exists() && !target.join(&command.rel_path).exists() {
+            println!("WARN: {} no longer resolves, {} is not in {}", command.alias, command.rel_path, path_to_str(&target));
+        }
+    }
+    if let Err(e) = runs::move_scope(&scope.path, &target) {
+        println!("WARN: unable to keep the recorded runs of the moved scripts: {e}");
+    }
+    if scope.kind == ScopeKind::GLOBAL {
+        let mut config = config::load().to_owned();
+        config.global_scope = Some(target.to_owned());
+        match config::save(&config) {
+            Ok(path) => println!("INFO: global-scope in {} now points to {}", path_to_str(&path), path_to_str(&target)),
+            Err(e) => println!("WARN: unable to set global-scope in the config, set it to {} by hand: {e}", path_to_str(&target)),
+        }
+    }
+}
+
+/// Prints where the scopes are looked for and which of them are in use.
+fn cmd_scope_info(global: &Scope, local: &Option<Scope>) {
+    let initialized = |scope: &Scope| match find_index(&scope.path) {
+        Ok(Some(_)) => "initialized".to_string(),
+        Ok(None) => "not initialized".to_string(),
+        Err(e) => format!("broken: {e}"),
+    };
+    let current_dir = std::env::current_dir().expect("unable to retrieve current directory");
+    println!("current directory: {}", path_to_str(&current_dir));
+    let origin = if config::load().global_scope.is_some() { "set in the config" } else { "directory of the executable" };
+    println!("global scope: {} ({origin}, {})", path_to_str(&global.path), initialized(global));
+    match local {
+        Some(scope) => println!("local scope: {} (nearest ancestor with .cmd, {})", path_to_str(&scope.path), initialized(scope)),
+        None => println!("local scope: none, no .cmd found in the current directory or its ancestors"),
+    }
+}
+
+/// Quotes the text for a POSIX shell.
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+/// Name usable for a POSIX shell function, i.e. letters, digits, and underscores not starting with a digit.
+fn is_function_name(alias: &str) -> bool {
+    alias.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && alias.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Prints a shell function per script, to be sourced without scriptman installed.
+/// The functions run the scripts and their dependencies in the current directory as scriptman does;
+/// shadowed aliases are left out.
+fn cmd_export_shell(groups: &[CmdGroup]) {
+    println!("# Generated by {} export-shell, regenerate it after changing the scripts.", program_name());
+    let mut exported: Vec<&String> = vec![];
+    for command in groups.iter().flat_map(|g| &g.commands) {
+        if exported.contains(&&command.alias) {
+            continue;
+        }
+        exported.push(&command.alias);
+        if !is_function_name(&command.alias) {
+            eprintln!("WARN: skipping '{}', it is not a valid shell function name", command.alias);
+            continue;
+        }
+        println!();
+        for line in command.description.lines().filter(|l| !l.trim().is_empty()) {
+            println!("# {line}");
+        }
+        println!("{}() {{", command.alias);
+        let defaults = |command: &Cmd| command.default_args.iter().map(|a| format!(" {}", shell_quote(a))).collect::<String>();
+        match resolve_dependencies(command, groups) {
+            Ok(dependencies) => for dependency in dependencies {
+                println!("    {}{} || return", shell_quote(&path_to_str(&dependency.abs_path)), defaults(&dependency));
+            },
+            Err(e) => eprintln!("WARN: exporting '{}' without its dependencies, {e}", command.alias),
+        }
+        if !command.default_args.is_empty() {
+            println!("    [ $# -eq 0 ] && set --{}", defaults(command));
+        }
+        println!("    {} \"$@\"", shell_quote(&path_to_str(&command.abs_path)));
+        println!("}}");
+    }
+}
+
+fn cmd_tag(alias: &String, tags: Vec<String>, clear: bool, groups: &mut [CmdGroup]) {
+    let Some(command) = find_command(alias, groups) else {
+        println!("{alias} is an unknown command");
+        return;
+    };
+    if tags.is_empty() && !clear {
+        command.tags.iter().for_each(|t| println!("{t}"));
+        return;
+    }
+    let group = groups.iter_mut().find(|g| g.scope == command.scope).expect("group of a found command");
+    for entry in group.commands.iter_mut().filter(|c| c.alias == *alias) {
+        entry.tags = tags.to_owned();
+    }
+    save_to_file(&index_path(&group.scope.path), group);
+}
+
+/// Runs every script with the tag in alias order, the exit code is the one of the first failure.
+fn cmd_run_all(tag: &String, keep_going: bool, options: &RunOptions, groups: &[CmdGroup]) -> i32 {
+    let mut commands: Vec<Cmd> = groups.iter()
+        .flat_map(|g| &g.commands)
+        .filter(|c| c.tags.contains(tag))
+        .cloned()
+        .collect();
+    commands.sort_by(|a, b| a.alias.cmp(&b.alias));
+    if commands.is_empty() {
+        println!("INFO: there are no scripts tagged {tag}");
+        return 0;
+    }
+    let mut failed: Vec<(String, i32)> = vec![];
+    let mut succeeded = 0;
+    for command in &commands {
+        println!("==> {}", command.alias);
+        let status = run_with_dependencies(command, vec![], options, groups);
+        if status.success() {
+            succeeded += 1;
+        } else {
+            failed.push((command.alias.to_owned(), status.code().unwrap_or(1)));
+            if !keep_going {
+                break;
+            }
+        }
+    }
+    let skipped = commands.len() - succeeded - failed.len();
+    println!("INFO: {succeeded} succeeded, {} failed, {skipped} not run", failed.len());
+    for (alias, code) in &failed {
+        println!("ERR: {alias} exited with code {code}");
+    }
+    failed.first().map(|(_, code)| *code).unwrap_or(0)
+}
+
+/// Only the group of the scope given by `-g` or `-l`, all of them without these flags.
+fn forced_scope_groups(cli_args: &ArgMatches, groups: &[CmdGroup]) -> Vec<CmdGroup> {
+    let forced = if cli_args.get_flag("global") {
+        Some(ScopeKind::GLOBAL)
+    } else if cli_args.get_flag("local") {
+        Some(ScopeKind::LOCAL)
+    } else {
+        None
+    };
+    groups.iter()
+        .filter(|g| forced.as_ref().is_none_or(|kind| g.scope.kind == *kind))
+        .cloned()
+        .collect()
+}
+
+/// Sorts the index by alias and rewrites it in the canonical formatting, `check` only reports a difference.
+fn cmd_fmt(check: bool, scope: &Scope) {
+    let Some(path) = find_index(&scope.path).unwrap_or_else(|e| panic!("{e}")) else {
+        println!("INFO: the {} scope has no index", scope.kind.name());
+        return;
+    };
+    let mut group = CmdGroup::new(scope).unwrap_or_else(|e| panic!("unable to load {}: {e}", path_to_str(&path)));
+    group.commands.sort_by(|a, b| a.alias.cmp(&b.alias));
+    let formatted = serialize_group(&path, &group).expect("unable to serialize the index");
+    let current = std::fs::read_to_string(&path).expect("unable to read the index");
+    if current == formatted {
+        return;
+    }
+    if check {
+        println!("ERR: {} is not formatted, run `fmt` to fix it", path_to_str(&path));
+        std::process::exit(1);
+    }
+    save_to_file(&path, &group);
+    println!("INFO: {} was formatted", path_to_str(&path));
+}
+
+fn parse_mode(mode: &str) -> std::result::Result<u32, String> {
+    match u32::from_str_radix(mode, 8) {
+        Ok(mode) if mode <= 0o7777 => Ok(mode),
+        _ => Err("expected an octal mode such as 0700 or 755".to_string()),
+    }
+}
+
+fn cmd_chmod(alias: &String, mode: u32, groups: &[CmdGroup]) {
+    let Some(command) = find_command(alias, groups) else {
+        println!("{alias} is an unknown command");
+        return;
+    };
+    if let Err(e) = std::fs::set_permissions(&command.abs_path, std::fs::Permissions::from_mode(mode)) {
+        panic!("unable to change permissions of {}: {e}", command.rel_path);
+    }
+}
+
+/// Marks the script as fresh by storing the current time into the index and as the mtime of
+/// its file, `fix_mode` also makes the file executable by whoever can read it.
+fn cmd_touch(alias: &String, fix_mode: bool, groups: &mut [CmdGroup]) {
+    let Some(command) = find_command(alias, groups) else {
+        println!("{alias} is an unknown command");
+        return;
+    };
+    let now = SystemTime::now();
+    let group = groups.iter_mut().find(|g| g.scope == command.scope).expect("group of a found command");
+    for entry in group.commands.iter_mut().filter(|c| c.alias == *alias) {
+        entry.updated_at = Some(now.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0));
+    }
+    save_to_file(&index_path(&group.scope.path), group);
+    let path = path_to_str(&command.abs_path);
+    if let Err(e) = std::fs::File::open(&command.abs_path).and_then(|file| file.set_modified(now)) {
+        println!("WARN: unable to update the modification time of {path}: {e}");
+        return;
+    }
+    if fix_mode {
+        let mode = std::fs::metadata(&command.abs_path).map(|m| m.permissions().mode()).unwrap_or(0);
+        let fixed = mode | (mode & 0o444) >> 2;
+        if fixed != mode {
+            match std::fs::set_permissions(&command.abs_path, std::fs::Permissions::from_mode(fixed)) {
+                Ok(()) => println!("INFO: {path} is executable again (mode {:o})", fixed & 0o7777),
+                Err(e) => println!("WARN: unable to change permissions of {path}: {e}"),
+            }
+        }
+    }
+}
+
+fn get_group_mut<'a>(scope_type: &ScopeKind, groups: &'a mut [CmdGroup]) -> Option<&'a mut CmdGroup> {
+    groups.iter_mut().find(|group| group.scope.kind == *scope_type)
+}
+
+fn find_command(alias: &String, groups: &[CmdGroup]) -> Option<Cmd> {
+    for group in groups {
+        for command in &group.commands {
+            if command.alias == *alias {
+                return Some(command.to_owned());
+            }
+        }
+    }
+    None
+}
+
+/// Description from a `# desc:` line, or the first comment line of the script if there is none.
+fn script_description(script_path: &PathBuf) -> Option<String> {
+    let data = std::fs::read_to_string(script_path).ok()?;
+    let mut first_comment = None;
+    for line in data.lines().map(|l| l.trim()) {
+        if let Some(description) = line.strip_prefix("# desc:") {
+            return Some(description.trim().to_string());
+        }
+        if first_comment.is_none() && !line.starts_with("#!") {
+            if let Some(comment) = line.strip_prefix('#') {
+                if !comment.trim().is_empty() {
+                    first_comment = Some(comment.trim().to_string());
+                }
+            }
+        }
+    }
+    first_comment
+}
+
+fn cmd_sync_descriptions(overwrite: bool, scope: &Scope, groups: &mut [CmdGroup]) {
+    let Some(group) = get_group_mut(&scope.kind, groups) else {
+        println!("INFO: there are no scripts in the {} scope", scope.kind.name());
+        return;
+    };
+    let mut updated = 0;
+    for command in &mut group.commands {
+        if !overwrite && !command.description.is_empty() {
+            continue;
+        }
+        if let Some(description) = script_description(&command.abs_path) {
+            if description != command.description {
+                println!("INFO: {}: {description}", command.alias);
+                command.description = description;
+                updated += 1;
+            }
+        }
+    }
+    if updated > 0 {
+        let path = index_path(&group.scope.path);
+        save_to_file(&path, group);
+    }
+    println!("INFO: updated {updated} description(s)");
+}
+
+/// Remembers the run of the script, failing to do so only warns.
+fn record_run(command: &Cmd) {
+    if let Err(e) = runs::record(command) {
+        eprintln!("WARN: unable to record the run of {}: {e}", command.alias);
+    }
+}
+
+/// Runs the script with the arguments, or with its default ones if there are none.
+/// The result of the run is returned rather than printed with `--capture-json`.
+fn cmd_run(command: &Cmd, args: Vec<String>, options: &RunOptions) -> (ExitStatus, Option<JsonResult>) {
+    let args = if args.is_empty() { command.default_args.to_owned() } else { args };
+    if !command.abs_path.exists() {
+        let alias = &command.alias;
+        let path_str = &command.rel_path;
+        let message = format!("the {alias} alias is pointed to a non-existant file {path_str}");
+        if options.json {
+            eprintln!("{message}");
+        } else {
+            println!("{message}");
+        }
+        std::process::exit(1);
+    }
+    let path = path_to_str(&command.abs_path);
+    let (program, args) = if options.via_shell {
+        let mut interpreter = script_interpreter(&command.abs_path);
+        let program = interpreter.remove(0);
+        interpreter.push(path.to_owned());
+        interpreter.extend(args);
+        (program, interpreter)
+    } else {
+        (path.to_owned(), args)
+    };
+    if options.json {
+        let (status, result) = execute_json(&command.alias, &program, args, options);
+        return (status, Some(result));
+    }
+    let result = match &options.capture {
+        Some(dir) => {
+            std::fs::create_dir_all(dir).expect("unable to create the capture directory");
+            let out_path = dir.join(format!("{}.out", file_stem(&command.alias)));
+            let err_path = dir.join(format!("{}.err", file_stem(&command.alias)));
+            execute_captured(&program, args, &options.envs, options.timeout, &out_path, &err_path, options.quiet)
+        },
+        None => execute(&program, args, &options.envs, options.timeout),
+    };
+    let status = match result {
+        Ok(status) => status,
+        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+            let seconds = options.timeout.map(|t| t.as_secs()).unwrap_or_default();
+            println!("ERR: command '{}' timed out after {seconds}s", command.alias);
+            // the exit code of coreutils `timeout`
+            ExitStatus::from_raw(124 << 8)
+        },
+        Err(_) => panic!("ERROR: Failed to execute command {path}"),
+    };
+    (status, None)
+}
+
+/// Interpreter with its arguments from the shebang of the script, `sh` if there is none.
+fn script_interpreter(script_path: &PathBuf) -> Vec<String> {
+    let data = std::fs::read_to_string(script_path).unwrap_or_default();
+    let shebang = data.lines().next().and_then(|l| l.strip_prefix("#!")).unwrap_or("");
+    let mut words: Vec<String> = shebang.split_whitespace().map(|w| w.to_string()).collect();
+    if words.first().is_some_and(|w| w.ends_with("/env")) {
+        words.remove(0);
+    }
+    if words.is_empty() {
+        words.push("sh".to_string());
+    }
+    words
+}
+
+/// Arguments piped to the script, one per line or separated by NUL bytes as from `find -print0`.
+fn read_stdin_args(null: bool) -> Vec<String> {
+    let mut data = vec![];
+    std::io::stdin().read_to_end(&mut data).expect("unable to read the arguments from stdin");
+    let separator = if null { b'\0' } else { b'\n' };
+    data.split(|b| *b == separator)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect()
+}
+
+/// Name under which the binary was invoked, as it is usually installed under a different name, e.g. `cmd`.
+fn program_name() -> String {
+    std::env::args_os().next()
+        .and_then(|arg| Path::new(&arg).file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or(env!("CARGO_PKG_NAME").to_string())
+}
+
+fn build_cli() -> Command {
+    command!()
+        .disable_help_flag(true)
+        .disable_help_subcommand(true)
+        .disable_version_flag(true)
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommands([
+            Command::new("init").visible_aliases(["--init", "-i"])
+            .arg(arg!(--git "Stage and commit the created scope in git"))
+            .arg(arg!(--"git-init" "Run git init first, implies --git"))
+            .arg(arg!(--"dry-run" "Only print what would be created").conflicts_with_all(["git", "git-init"]))
+            .about("Setup local scope in the current directory"),
+            Command::new("add").visible_aliases(["--add", "-a"])
+            .arg(arg!([ALIAS]).value_hint(ValueHint::Other).required_unless_present("interactive"))
+            .arg(arg!(-i --interactive "Ask for the alias, description, tags, and shell of the script"))
+            .arg(arg!(--"from-url" <URL> "Download the script instead of opening the editor").value_hint(ValueHint::Url).conflicts_with("interactive"))
+            .arg(arg!(--trust "Save the downloaded script, without it only its beginning is shown").requires("from-url"))
+            .arg(arg!([DESCRIPTION]))
+            .arg(arg!(--group <NAME> "Category under which the script is listed"))
+            .arg(arg!(--depends <ALIAS> ... "Scripts which have to succeed before this one is run").value_delimiter(','))
+            .arg(arg!(--"arg-spec" <KINDS> "Comma separated kinds of the script arguments used for their completion")
+                .value_parser(["file", "dir", "any"]).value_delimiter(','))
+            .arg(arg!(--readonly "Protect the script from accidental edits, see lock"))
+            .arg(arg!(--chmod <MODE> "Octal permissions of the created script, 0775 by default").value_parser(parse_mode))
+            .arg(arg!(--usage <USAGE> "How the script is called, e.g. '<file> [--force]', see list --usage"))
+            .arg(arg!(--"default-args" <ARGS> "Arguments used when the script is run without any, e.g. '--verbose --fast'")
+                .value_parser(split_words).allow_hyphen_values(true))
+            .arg(arg!(--tag <TAG> ... "Labels of the script, see run-all").value_delimiter(','))
+            .arg(arg!(--path <FILE> "Location of the script, e.g. an existing one in the repository, instead of .cmd/scripts")
+                .value_parser(clap::value_parser!(PathBuf)).value_hint(ValueHint::FilePath).conflicts_with("from-url"))
+            .arg(arg!(--"no-stub" "Only register the script, do not create its file nor open the editor")
+                .conflicts_with_all(["interactive", "from-url"]))
+            .arg(arg!(--template <FILE> "Create the script from the file, or one of .cmd/templates, instead of the stub")
+                .value_hint(ValueHint::FilePath).conflicts_with_all(["interactive", "from-url", "no-stub"]))
+            .arg(arg!(--"template-var" <PAIR> "Value of the {{KEY}} placeholder of the template as KEY=VALUE, may be repeated")
+                .value_parser(parse_env_pair).action(ArgAction::Append).requires("template"))
+            .arg(arg!(--strict "Fail if a placeholder of the template has no value").requires("template"))
+            .about("Create script and open it in the $EDITOR"),
+            Command::new("record").visible_alias("--record")
+            .arg(arg!(<ALIAS>).value_hint(ValueHint::Other))
+            .arg(arg!([DESCRIPTION]))
+            .about("Create script from commands typed in an interactive $SHELL"),
+            Command::new("import-aliases").visible_alias("--import-aliases")
+            .arg(arg!(<FILE>).value_parser(clap::value_parser!(PathBuf)).value_hint(ValueHint::FilePath))
+            .about("Create scripts from alias name='command' lines of a file, e.g. .bashrc"),
+            Command::new("edit").visible_aliases(["--edit", "-e"])
+            .arg(arg!([ALIAS]).value_parser(clap::value_parser!(String)))
+            .arg(arg!(--all "Open all scripts of the scope at once").conflicts_with("ALIAS"))
+            .arg(arg!(--force "Edit also readonly scripts"))
+            .about("Open script index or [SCRIPT] in the $EDITOR"),
+            Command::new("lock").visible_alias("--lock")
+            .arg(arg!(<ALIAS>).value_hint(ValueHint::Other))
+            .about("Make script readonly, editing it then requires --force"),
+            Command::new("unlock").visible_alias("--unlock")
+            .arg(arg!(<ALIAS>).value_hint(ValueHint::Other))
+            .about("Make readonly script editable again"),
+            Command::new("remove").visible_aliases(["--remove", "-r"])
+            .arg(arg!(<ALIAS>).value_hint(ValueHint::Other))
+            .arg(arg!(--force "Remove also a script other scripts depend on"))
+            .arg(arg!(--purge "Delete also the script file if it is within .cmd"))
+            .arg(arg!(--"dry-run" "Only print what would be removed"))
+            .about("Remove script from the index (does NOT remove file without --purge)"),
+            Command::new("rename").visible_aliases(["--rename", "--move"])
+            .arg(arg!(<ALIAS>).value_hint(ValueHint::Other))
+            .arg(arg!(<NEW_ALIAS>).value_hint(ValueHint::Other))
+            .about("Change alias of a script and the dependencies on it (does NOT rename file)"),
+            Command::new("describe").visible_alias("--describe")
+            .arg(arg!(<ALIAS>).value_hint(ValueHint::Other))
+            .arg(arg!([DESCRIPTION] "New description, edit the current one in place if not given"))
+            .about("Change description of the script"),
+            Command::new("depends").visible_alias("--depends")
+            .arg(arg!(<ALIAS>).value_hint(ValueHint::Other))
+            .arg(arg!([DEPENDENCY] ... "Scripts which have to succeed before ALIAS is run, print the current ones if not given"))
+            .arg(arg!(--clear "Remove all dependencies"))
+            .about("Show or set dependencies of the script"),
+            Command::new("tag").visible_alias("--tag")
+            .arg(arg!(<ALIAS>).value_hint(ValueHint::Other))
+            .arg(arg!([TAG] ... "Tags of the script, print the current ones if not given"))
+            .arg(arg!(--clear "Remove all tags"))
+            .about("Show or set tags of the script"),
+            Command::new("run").visible_alias("--run")
+            .arg(arg!(<ALIAS>).value_hint(ValueHint::Other))
+            .arg(arg!([args]...).trailing_var_arg(true).allow_hyphen_values(true))
+            .about("Run the script, also one whose alias is taken by a builtin"),
+            Command::new("run-all").visible_alias("--run-all")
+            .arg(arg!(<TAG>))
+            .arg(arg!(--"keep-going" "Run the remaining scripts also after a failure"))
+            .about("Run all scripts with the tag in alias order"),
+            Command::new("diff").visible_alias("--diff")
+            .arg(arg!(<ALIAS>).value_hint(ValueHint::Other))
+            .about("Show uncommitted changes of the script in git"),
+            Command::new("swap").visible_alias("--swap")
+            .arg(arg!(<FIRST>).value_hint(ValueHint::Other))
+            .arg(arg!(<SECOND>).value_hint(ValueHint::Other))
+            .arg(arg!(--files "Exchange also the script files so that they keep matching the aliases"))
+            .about("Exchange aliases of two scripts of the same scope"),
+            Command::new("check-perms").visible_alias("--check-perms")
+            .arg(arg!(--fix "Remove the group and world write permission"))
+            .about("Warn about scripts writable by other users"),
+            Command::new("validate-all").visible_alias("--validate-all")
+            .about("Check the indexes of the global scope and of all local ones around the current directory"),
+            Command::new("lint").visible_alias("--lint")
+            .arg(arg!([ALIAS]).value_parser(clap::value_parser!(String)))
+            .about("Check the script, or all scripts, with shellcheck"),
+            Command::new("sync-descriptions").visible_alias("--sync-descriptions")
+            .arg(arg!(--overwrite "Replace also descriptions which are not empty"))
+            .about("Fill descriptions from '# desc:' or first comment lines of the scripts"),
+            Command::new("list").visible_alias("--list")
+            .arg(arg!(--plain "Print only the aliases, one per line"))
+            .arg(arg!(--full "Do not truncate descriptions to the terminal width"))
+            .arg(arg!(--width <N> "Truncate descriptions to fit N columns").value_parser(clap::value_parser!(usize)))
+            .arg(arg!(--recent [N] "Show only the N (default 10) most recently run scripts, newest first")
+                .value_parser(clap::value_parser!(usize)).default_missing_value("10"))
+            .arg(arg!(--popular [N] "Show only the N (default 10) most often run scripts, most runs first")
+                .value_parser(clap::value_parser!(usize)).default_missing_value("10").conflicts_with("recent"))
+            .arg(arg!(--group <NAME> "Show only scripts of the group, ungrouped ones are in 'misc'"))
+            .arg(arg!(--long "Show also size, modification time, and line count of the scripts"))
+            .arg(arg!(--usage "Print how each script is called instead of its description").conflicts_with_all(["plain", "long"]))
+            .arg(arg!(--sort <KEY> "Order of the scripts, by size the largest first, by updated the freshest first").value_parser(["alias", "size", "updated"]))
+            .arg(arg!(--columns <NAMES> "Comma separated fields to show in this order, alias,scope,description by default")
+                .value_parser(["alias", "scope", "description", "path", "tags"]).value_delimiter(',')
+                .conflicts_with_all(["plain", "long", "usage"]))
+            .about("List scripts of all active scopes"),
+            Command::new("completions").visible_alias("--completions")
+            .arg(arg!([SHELL]).value_parser(clap::value_parser!(Shell)).required_unless_present("shells"))
+            .arg(arg!(--shells "Print the supported shells, one per line").exclusive(true))
+            .arg(arg!(--dynamic "Query the current scripts when completing instead of a static snapshot, the default for bash"))
+            .arg(arg!(--static "Generate a snapshot of the current scripts even for bash").conflicts_with("dynamic"))
+            .arg(arg!(--for <ALIAS> "Print only completion of the arguments of the given script").conflicts_with("dynamic"))
+            .arg(arg!(--install "Write the completions into the usual place of the shell instead of printing them").conflicts_with("for"))
+            .arg(arg!(--"print-path" "Print only the path where --install writes the completions").conflicts_with_all(["for", "install", "merge"]))
+            .arg(arg!(--uninstall "Remove the completions written by --install or --merge").conflicts_with_all(["for", "install", "merge", "print-path"]))
+            .arg(arg!(--merge <FILE> "Install the completions as a marked block within FILE, e.g. ~/.bashrc, replacing the previous block")
+                .value_parser(clap::value_parser!(PathBuf)).value_hint(ValueHint::FilePath).conflicts_with("for"))
+            .about("Print shell completion script"),
+            Command::new("chmod").visible_alias("--chmod")
+            .arg(arg!(<ALIAS>).value_hint(ValueHint::Other))
+            .arg(arg!(<MODE> "Octal permissions, e.g. 0700").value_parser(parse_mode))
+            .about("Change permissions of the script file"),
+            Command::new("touch").visible_alias("--touch")
+            .arg(arg!(<ALIAS>).value_hint(ValueHint::Other))
+            .arg(arg!(--"fix-mode" "Make the script executable again where it is readable"))
+            .about("Mark script as updated without changing its content"),
+            Command::new("fmt").visible_alias("--fmt")
+            .arg(arg!(--check "Only fail if the index is not formatted"))
+            .about("Sort the index by alias and normalize its formatting"),
+            Command::new("backup").visible_alias("--backup")
+            .arg(arg!(--full "Back up also the scripts, not only the index"))
+            .arg(arg!(--list "List the backups instead of creating one").conflicts_with("full"))
+            .about("Copy the index of the scope into .cmd/backups"),
+            Command::new("restore").visible_alias("--restore")
+            .arg(arg!(<TIMESTAMP> "Name of the backup as listed by `backup --list`").value_hint(ValueHint::Other))
+            .about("Replace the index, or the whole scope for a full backup, by the backup"),
+            Command::new("where").visible_alias("--where")
+            .about("Show the path of the executable, of the global scope, and of the config"),
+            Command::new("config").visible_alias("--config")
+            .arg(arg!([KEY] "Name of the setting").value_parser(config::KEYS).required_unless_present("list"))
+            .arg(arg!([VALUE] "New value of the setting"))
+            .arg(arg!(--unset "Remove the setting").requires("KEY").conflicts_with("VALUE"))
+            .arg(arg!(--list "Print all settings").conflicts_with_all(["KEY", "unset"]))
+            .about("Show or change a user setting, e.g. the editor"),
+            Command::new("rename-scope-path").visible_alias("--rename-scope-path")
+            .arg(arg!(<DIR> "Directory to move the .cmd folder into").value_parser(clap::value_parser!(PathBuf)).value_hint(ValueHint::DirPath))
+            .about("Move the scripts of the scope to another directory"),
+            Command::new("scope-info").visible_alias("--scope-info")
+            .about("Show the paths of the global and local scope"),
+            Command::new("export-shell").visible_alias("--export-shell")
+            .about("Print the scripts as shell functions to source without scriptman"),
+            Command::new("man").visible_alias("--man")
+            .arg(arg!(--scripts "Add a section listing the scripts of the active scopes"))
+            .about("Print a manual page, e.g. for `man -l -`"),
+            Command::new("version").visible_alias("--version")
+            .about("Prints out version information"),
+            Command::new("self-check").alias("--self-check").hide(true)
+            .about("Check all scopes around and that their scripts load, e.g. in CI"),
+            Command::new("complete-commands").alias("--complete-commands").hide(true)
+            .about("Print `alias<TAB>description` lines of all scripts for external completion scripts"),
+        ])
+        .args([
+            arg!(-l --local "Force local scope"),
+            arg!(-g --global "Force global scope"),
+            arg!(--capture <DIR> "Save stdout and stderr of the script into DIR/<alias>.out and .err")
+            .value_parser(clap::value_parser!(PathBuf)).value_hint(ValueHint::DirPath),
+            arg!(--"capture-json" "Print the exit code, output, and duration of the script as a json line")
+            .conflicts_with("capture"),
+            arg!(-q --quiet "Do not echo the captured output to the terminal nor print hints"),
+            arg!(--"update-completions" "Regenerate the installed completions when scripts are added or removed"),
+            arg!(--"via-shell" "Run scripts through the interpreter of their shebang instead of executing them directly"),
+            arg!(--"stdin-args" "Append arguments read from stdin, one per line, to the arguments of the script"),
+            Arg::new("null").short('0').long("null").action(ArgAction::SetTrue).requires("stdin-args")
+            .help("Separate the arguments read from stdin by NUL bytes instead of newlines"),
+            arg!(--timeout <SECONDS> "Terminate the script if it runs longer, it then exits with code 124")
+            .value_parser(clap::value_parser!(u64)),
+            arg!(--"env-file" <FILE> "Set variables of a dotenv-style FILE for the script")
+            .value_parser(clap::value_parser!(PathBuf)).value_hint(ValueHint::FilePath),
+            arg!(--env <VAR> "Set KEY=VALUE for the script, overrides the --env-file, may be repeated")
+            .value_parser(parse_env_pair).action(ArgAction::Append),
+        ].map(|x|x.required(false)))
+}
+
+/// Builtins together with a subcommand per script; clap panics on a name used twice, so scripts
+/// whose alias is taken by a builtin or an earlier script are left out with a warning.
+fn cli_with_scripts(groups: &[CmdGroup]) -> Command {
+    let mut builder = build_cli();
+    for command in groups.iter().flat_map(|g| &g.commands) {
+        if builder.find_subcommand(&command.alias).is_some() {
+            if is_builtin(&command.alias) {
+                eprintln!("WARN: script '{}' of the {} scope is shadowed by the builtin command, run it by `{} run {}`",
+                    command.alias, command.scope.kind.name(), program_name(), command.alias);
+            } else {
+                eprintln!("WARN: ignoring script '{}' of the {} scope, its alias is used by another script", command.alias, command.scope.kind.name());
+            }
+            continue;
+        }
+        builder = builder.subcommand(
+            Command::new(&command.alias)
+            .about(&command.description)
+            .arg(arg!([args]...))
+            );
+    }
+    builder
+}
+
+/// Static completions list the scripts, so after their change they are regenerated or the user is told.
+fn refresh_completions(cli_args: &ArgMatches, groups: &[CmdGroup]) {
+    let bin_name = program_name();
+    let stale: Vec<completions::Installed> = completions::installed(&bin_name).into_iter()
+        .filter(|i| !i.dynamic)
+        .collect();
+    if stale.is_empty() {
+        return;
+    }
+    if cli_args.get_flag("update-completions") {
+        for entry in stale {
+            let merge = entry.merged.then_some(entry.path.as_path());
+            if let Err(e) = completions::install(entry.shell, false, merge, &bin_name, &mut cli_with_scripts(groups)) {
+                println!("WARN: unable to update {} completions: {e}", entry.shell);
+            }
+        }
+    } else if !cli_args.get_flag("quiet") {
+        let shells: Vec<String> = stale.iter().map(|i| i.shell.to_string()).collect();
+        println!("INFO: installed {} completions are out of date, pass --update-completions to regenerate them", shells.join(", "));
+    }
+}
+
+/// Scripts of the active scopes, each index is parsed once and shared by the whole run.
+fn load_groups(global_scope: &Scope, local_scope: Option<&Scope>) -> Vec<CmdGroup> {
+    let mut cmd_groups: Vec<CmdGroup> = vec![];
+    if let Ok(global) = CmdGroup::new(global_scope) {
+        cmd_groups.push(global);
+    }
+    if let Some(scope) = local_scope {
+        match CmdGroup::new(scope) {
+            Ok(commands) => cmd_groups.push(commands),
+            Err(e) => println!("ERR: {:?}", e),
+        }
+    }
+    cmd_groups
+}
+
+fn main() {
+    let global_scope = Scope{kind: ScopeKind::GLOBAL, path: find_global_dir()};
+    let local_scope = find_local_dir().map(|local_dir| Scope{kind: ScopeKind::LOCAL, path: local_dir});
+    let mut cmd_groups = load_groups(&global_scope, local_scope.as_ref());
+    // broken scripts may make building the commands panic, so the check is recognized before
+    if std::env::args().any(|arg| arg == "self-check" || arg == "--self-check")
+        && build_cli().try_get_matches().is_ok_and(|m| m.subcommand_name() == Some("self-check")) {
+        validate::cmd_self_check(&global_scope, &cmd_groups);
+        return;
+    }
+    let mut builder = cli_with_scripts(&cmd_groups);
+    let cli_args = builder.get_matches_mut();
+    let (subcommand, matched_args) = match cli_args.subcommand() {
+        Some((subcommand, matched_args)) => (subcommand, matched_args),
+        None => return,
+    };
+    match subcommand {
+        "init" => {
+            if matched_args.get_flag("dry-run") {
+                cmd_init_dry_run();
+            } else {
+                cmd_init_local(matched_args.get_flag("git"), matched_args.get_flag("git-init"));
+            }
+        },
+        "add" => {
+            let scope = choose_scope(&cli_args, global_scope, local_scope);
+            let options = AddOptions::new(matched_args);
+            if matched_args.get_flag("interactive") {
+                let alias = matched_args.get_one::<String>("ALIAS");
+                cmd_add_interactive(alias, matched_args.get_one::<String>("DESCRIPTION"), &options, &scope, &mut cmd_groups);
+            } else {
+                let alias: &String = matched_args.get_one::<String>("ALIAS").unwrap();
+                let empty = "".to_string();
+                let description: &String = matched_args.get_one::<String>("DESCRIPTION").unwrap_or(&empty);
+                match matched_args.get_one::<String>("from-url") {
+                    Some(url) => cmd_add_from_url(alias, description, url, matched_args.get_flag("trust"), &options, &scope, &mut cmd_groups),
+                    None => cmd_add(alias, description, &options, &scope, &mut cmd_groups),
+                }
+            }
+            refresh_completions(&cli_args, &cmd_groups);
+        },
+        "record" => {
+            let alias: &String = matched_args.get_one::<String>("ALIAS").unwrap();
+            let empty = "".to_string();
+            let description: &String = matched_args.get_one::<String>("DESCRIPTION").unwrap_or(&empty);
+            let scope = choose_scope(&cli_args, global_scope, local_scope);
+            cmd_record(alias, description, &scope, &mut cmd_groups);
+        },
+        "import-aliases" => {
+            let file = matched_args.get_one::<PathBuf>("FILE").unwrap();
+            let scope = choose_scope(&cli_args, global_scope, local_scope);
+            cmd_import_aliases(file, &scope, &mut cmd_groups);
+        },
+        "edit" => {
+            let some_alias = matched_args.get_one::<String>("ALIAS");
+            let scope = choose_scope(&cli_args, global_scope, local_scope);
+            cmd_edit(some_alias, matched_args.get_flag("all"), matched_args.get_flag("force"), &scope, &cmd_groups);
+        },
+        "lock" | "unlock" => {
+            let alias = matched_args.get_one::<String>("ALIAS").unwrap();
+            cmd_lock(alias, subcommand == "lock", &mut cmd_groups);
+        },
+        "remove" => {
+            let alias = matched_args.get_one::<String>("ALIAS").unwrap();
+            let (purge, dry_run) = (matched_args.get_flag("purge"), matched_args.get_flag("dry-run"));
+            if cmd_remove(alias, matched_args.get_flag("force"), purge, dry_run, &mut cmd_groups) {
+                refresh_completions(&cli_args, &cmd_groups);
+            }
+        },
+        "rename" => {
+            let alias = matched_args.get_one::<String>("ALIAS").unwrap();
+            let new_alias = matched_args.get_one::<String>("NEW_ALIAS").unwrap();
+            if cmd_rename(alias, new_alias, &mut cmd_groups) {
+                refresh_completions(&cli_args, &cmd_groups);
+            }
+        },
+        "describe" => {
+            let alias = matched_args.get_one::<String>("ALIAS").unwrap();
+            cmd_describe(alias, matched_args.get_one::<String>("DESCRIPTION"), &mut cmd_groups);
+        },
+        "depends" => {
+            let alias = matched_args.get_one::<String>("ALIAS").unwrap();
+            let dependencies = matched_args.get_many::<String>("DEPENDENCY").unwrap_or_default().cloned().collect();
+            cmd_depends(alias, dependencies, matched_args.get_flag("clear"), &mut cmd_groups);
+        },
+        "tag" => {
+            let alias = matched_args.get_one::<String>("ALIAS").unwrap();
+            let tags = matched_args.get_many::<String>("TAG").unwrap_or_default().cloned().collect();
+            cmd_tag(alias, tags, matched_args.get_flag("clear"), &mut cmd_groups);
+        },
+        "run" => {
+            let alias = matched_args.get_one::<String>("ALIAS").unwrap();
+            let Some(command) = find_command(alias, &cmd_groups) else {
+                println!("{alias} is an unknown command");
+                std::process::exit(1);
+            };
+            run_script(&command, matched_args, &cli_args, &cmd_groups);
+        },
+        "run-all" => {
+            let tag = matched_args.get_one::<String>("TAG").unwrap();
+            let code = cmd_run_all(tag, matched_args.get_flag("keep-going"), &RunOptions::new(&cli_args), &cmd_groups);
+            std::process::exit(code);
+        },
+        "diff" => {
+            cmd_diff(matched_args.get_one::<String>("ALIAS").unwrap(), &cmd_groups);
+        },
+        "swap" => {
+            let first = matched_args.get_one::<String>("FIRST").unwrap();
+            let second = matched_args.get_one::<String>("SECOND").unwrap();
+            cmd_swap(first, second, matched_args.get_flag("files"), &mut cmd_groups);
+        },
+        "check-perms" => {
+            cmd_check_perms(matched_args.get_flag("fix"), &cmd_groups);
+        },
+        "validate-all" => {
+            validate::cmd_validate_all(&global_scope);
+        },
+        "lint" => {
+            cmd_lint(matched_args.get_one::<String>("ALIAS"), &forced_scope_groups(&cli_args, &cmd_groups));
+        },
+        "sync-descriptions" => {
+            let scope = choose_scope(&cli_args, global_scope, local_scope);
+            cmd_sync_descriptions(matched_args.get_flag("overwrite"), &scope, &mut cmd_groups);
+        },
+        "list" => {
+            list::cmd_list(&list::ListOptions::new(matched_args), &forced_scope_groups(&cli_args, &cmd_groups));
+        },
+        "completions" => {
+            if matched_args.get_flag("shells") {
+                completions::print_shells();
+                return;
+            }
+            let shell = *matched_args.get_one::<Shell>("SHELL").unwrap();
+            let dynamic = matched_args.get_flag("dynamic") || (shell == Shell::Bash && !matched_args.get_flag("static"));
+            if dynamic && !completions::DYNAMIC_SHELLS.contains(&shell) {
+                println!("ERR: dynamic completions are supported only for bash, zsh, and fish, not {shell}");
+                std::process::exit(1);
+            }
+            if let Some(alias) = matched_args.get_one::<String>("for") {
+                if !completions::ARG_COMPLETION_SHELLS.contains(&shell) {
+                    println!("ERR: completion of a single script is supported only for bash and zsh, not {shell}");
+                    std::process::exit(1);
+                }
+                match find_command(alias, &cmd_groups) {
+                    Some(command) => completions::print_arg_completions(shell, &program_name(), &command),
+                    None => println!("{alias} is an unknown command"),
+                }
+            } else if matched_args.get_flag("uninstall") {
+                match completions::uninstall(shell, &program_name()) {
+                    Ok(Some(path)) => println!("INFO: completions removed from {}", path_to_str(&path)),
+                    Ok(None) => println!("INFO: no installed {shell} completions were found"),
+                    Err(e) => panic!("unable to uninstall completions: {e}"),
+                }
+            } else if matched_args.get_flag("print-path") {
+                match completions::install_path(shell, &program_name()) {
+                    Some(path) => println!("{}", path_to_str(&path)),
+                    None => {
+                        println!("ERR: there is no known install location of completions for {shell}");
+                        std::process::exit(1);
+                    },
+                }
+            } else if matched_args.get_flag("install") || matched_args.contains_id("merge") {
+                let merge = matched_args.get_one::<PathBuf>("merge").map(PathBuf::as_path);
+                match completions::install(shell, dynamic, merge, &program_name(), &mut builder) {
+                    Ok(path) => println!("INFO: completions installed into {}", path_to_str(&path)),
+                    Err(e) => panic!("unable to install completions: {e}"),
+                }
+            } else {
+                completions::print_completions(shell, dynamic, &program_name(), &mut builder);
+            }
+        },
+        "chmod" => {
+            let alias = matched_args.get_one::<String>("ALIAS").unwrap();
+            cmd_chmod(alias, *matched_args.get_one::<u32>("MODE").unwrap(), &cmd_groups);
+        },
+        "touch" => {
+            let alias = matched_args.get_one::<String>("ALIAS").unwrap();
+            cmd_touch(alias, matched_args.get_flag("fix-mode"), &mut forced_scope_groups(&cli_args, &cmd_groups));
+        },
+        "fmt" => {
+            let scope = choose_scope(&cli_args, global_scope, local_scope);
+            cmd_fmt(matched_args.get_flag("check"), &scope);
+        },
+        "backup" => {
+            let scope = choose_scope(&cli_args, global_scope, local_scope);
+            if matched_args.get_flag("list") {
+                backup::cmd_list_backups(&scope);
+            } else {
+                backup::cmd_backup(&scope, matched_args.get_flag("full"));
+            }
+        },
+        "restore" => {
+            let scope = choose_scope(&cli_args, global_scope, local_scope);
+            backup::cmd_restore(&scope, matched_args.get_one::<String>("TIMESTAMP").unwrap());
+        },
+        "config" => {
+            let key = matched_args.get_one::<String>("KEY");
+            config::cmd_config(key, matched_args.get_one::<String>("VALUE"), matched_args.get_flag("unset"), matched_args.get_flag("list"));
+        },
+        "where" => {
+            cmd_where(&global_scope);
+        },
+        "rename-scope-path" => {
+            let scope = choose_scope(&cli_args, global_scope, local_scope);
+            cmd_rename_scope_path(&scope, matched_args.get_one::<PathBuf>("DIR").unwrap());
+        },
+        "scope-info" => {
+            cmd_scope_info(&global_scope, &local_scope);
+        },
+        "export-shell" => {
+            cmd_export_shell(&forced_scope_groups(&cli_args, &cmd_groups));
+        },
+        "man" => {
+            let scripts = forced_scope_groups(&cli_args, &cmd_groups);
+            completions::print_man(&program_name(), matched_args.get_flag("scripts").then_some(scripts.as_slice()));
+        },
+        "version" => {
+            print!("{}", builder.render_version());
+        },
+        "complete-commands" => {
+            completions::print_command_lines(&forced_scope_groups(&cli_args, &cmd_groups));
+        },
+        _ => {
+            if let Some(command) = find_command(&(*subcommand).into(), &cmd_groups) {
+                run_script(&command, matched_args, &cli_args, &cmd_groups);
+            } else {
+                panic!("unknown subcommand returned from parser");
+            }
+        },
+    }
+}
+
+/// Runs the script with the arguments matched by its subcommand or by `run`, exits with its code on a failure.
+fn run_script(command: &Cmd, matched_args: &ArgMatches, cli_args: &ArgMatches, groups: &[CmdGroup]) {
+    let mut args: Vec<String> = match matched_args.get_many::<String>("args") {
+        Some(s) => s.into_iter().map(|s|s.to_string()).collect(),
+        None => vec![],
+    };
+    if cli_args.get_flag("stdin-args") {
+        args.extend(read_stdin_args(cli_args.get_flag("null")));
+    }
+    let status = run_with_dependencies(command, args, &RunOptions::new(cli_args), groups);
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty directory in the temporary one, unique for the test.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}-unit-{}-{name}", env!("CARGO_PKG_NAME"), std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn index_round_trips_in_each_format() {
+        let dir = temp_dir("round-trip");
+        let json = r#"[
+            {"alias": "build", "rel_path": "./.cmd/scripts/build.sh", "description": "Build it", "tags": ["ci"], "run_count": 2},
+            {"alias": "deploy", "rel_path": "./.cmd/scripts/deploy.sh", "description": "", "depends": ["build"], "default_args": ["--prod"], "readonly": true}
+        ]"#;
+        let commands = IndexFormat::Json.deserialize(json).unwrap();
+        let expected = IndexFormat::Json.serialize(&commands).unwrap();
+        for format in IndexFormat::ALL {
+            let scope = Scope{kind: ScopeKind::LOCAL, path: dir.join(format.file_name())};
+            std::fs::create_dir_all(scope.path.join(".cmd")).unwrap();
+            let path = scope.path.join(".cmd").join(format.file_name());
+            let data = format.serialize(&commands).unwrap();
+            std::fs::write(&path, &data).unwrap();
+            let group = CmdGroup::new(&scope).unwrap();
+            save_to_file(&index_path(&scope.path), &group);
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), data, "{format:?}");
+            let reloaded: Vec<JsonCmd> = CmdGroup::new(&scope).unwrap().commands.iter().map(|c|c.into()).collect();
+            assert_eq!(IndexFormat::Json.serialize(&reloaded).unwrap(), expected, "{format:?}");
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn several_index_files_are_an_error() {
+        let dir = temp_dir("several-indexes");
+        std::fs::create_dir_all(dir.join(".cmd")).unwrap();
+        std::fs::write(dir.join(".cmd/index.json"), "[]").unwrap();
+        std::fs::write(dir.join(".cmd/index.yaml"), "[]").unwrap();
+        let error = find_index(&dir).unwrap_err().to_string();
+        assert!(error.starts_with("multiple index files found"), "{error}");
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
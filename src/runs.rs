@@ -0,0 +1,104 @@
+use anyhow::Result;
+use serde_derive::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{path_to_str, Cmd};
+
+/// Record of the runs of a script through scriptman.
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+pub struct Runs {
+    /// Seconds since the unix epoch of the last run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last: Option<u64>,
+    /// Number of the runs.
+    #[serde(default)]
+    pub count: u64,
+}
+
+/// Runs by the path of the scope and the path of the script relative to it. They are kept apart from
+/// the indexes, so that running a script does not rewrite an index which may be committed or edited.
+type State = BTreeMap<String, BTreeMap<String, Runs>>;
+
+fn state_home() -> Option<PathBuf> {
+    match std::env::var_os("XDG_STATE_HOME") {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")),
+    }
+}
+
+pub fn path() -> Option<PathBuf> {
+    state_home().map(|dir| dir.join(env!("CARGO_PKG_NAME")).join("runs.json"))
+}
+
+fn try_load(path: &Path) -> Result<State> {
+    if !path.exists() {
+        return Ok(State::new());
+    }
+    let data = std::fs::read_to_string(path)?;
+    serde_json::from_str(&data).map_err(|e| anyhow::anyhow!("{} is invalid: {e}", path_to_str(&path.to_path_buf())))
+}
+
+/// The recorded runs, read once per run; problems are reported on stderr like those of the config.
+fn load() -> &'static State {
+    static STATE: OnceLock<State> = OnceLock::new();
+    STATE.get_or_init(|| {
+        let Some(path) = path() else {
+            return State::new();
+        };
+        try_load(&path).unwrap_or_else(|e| {
+            eprintln!("WARN: ignoring the recorded runs, {e}");
+            State::new()
+        })
+    })
+}
+
+/// Recorded runs of the script of the scope, if it was run.
+pub fn of(scope_path: &PathBuf, rel_path: &str) -> Option<Runs> {
+    load().get(&path_to_str(scope_path)).and_then(|scripts| scripts.get(rel_path)).copied()
+}
+
+/// Holds an advisory lock of the state until it is dropped, so that concurrent runs do not lose
+/// each other's changes.
+fn lock(path: &Path) -> Result<std::fs::File> {
+    let file = std::fs::OpenOptions::new().create(true).truncate(false).write(true).open(path.with_extension("lock"))?;
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(file)
+}
+
+/// Counts a run of the script; the file is read again under the lock right before it is written,
+/// so that only the entry of the script changes.
+pub fn record(command: &Cmd) -> Result<()> {
+    let path = path().ok_or(anyhow::anyhow!("neither XDG_STATE_HOME nor HOME is set"))?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let _lock = lock(&path)?;
+    let mut state = try_load(&path)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let runs = state.entry(path_to_str(&command.scope.path)).or_default()
+        .entry(command.rel_path.to_owned())
+        .or_insert(Runs{last: command.last_run, count: command.run_count});
+    runs.last = Some(now);
+    runs.count += 1;
+    crate::write_replacing(&path, serde_json::to_string_pretty(&state)?)
+}
+
+/// Keeps the runs of the scripts of a scope whose directory was moved.
+pub fn move_scope(from: &PathBuf, to: &PathBuf) -> Result<()> {
+    let Some(path) = path().filter(|p| p.exists()) else {
+        return Ok(());
+    };
+    let _lock = lock(&path)?;
+    let mut state = try_load(&path)?;
+    let Some(scripts) = state.remove(&path_to_str(from)) else {
+        return Ok(());
+    };
+    state.insert(path_to_str(to), scripts);
+    crate::write_replacing(&path, serde_json::to_string_pretty(&state)?)
+}
@@ -0,0 +1,206 @@
+use std::path::{Path, PathBuf};
+
+use crate::{cli_with_scripts, find_index, is_builtin, load_from_file, normalize, path_to_str, CmdGroup, Scope, ScopeKind};
+
+/// How many directory levels below the current one are searched for scopes.
+const MAX_DEPTH: usize = 6;
+
+/// Build and dependency directories, which are large and never hold scopes of their own.
+const SKIPPED_DIRS: [&str; 6] = ["target", "node_modules", "build", "dist", "venv", "__pycache__"];
+
+fn is_git_root(dir: &Path) -> bool {
+    dir.join(".git").symlink_metadata().is_ok()
+}
+
+/// Directories with a `.cmd` among the current one, its ancestors up to the git root, and its
+/// descendants up to `MAX_DEPTH` levels; hidden, build, and symlinked directories and nested
+/// git repositories are not descended into.
+fn local_scopes() -> Vec<PathBuf> {
+    fn descend(dir: &Path, depth: usize, found: &mut Vec<PathBuf>) {
+        if depth == MAX_DEPTH {
+            return;
+        }
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        let mut dirs: Vec<PathBuf> = entries.flatten()
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+            .filter(|e| {
+                let name = e.file_name().to_string_lossy().into_owned();
+                !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_str())
+            })
+            .map(|e| e.path())
+            .collect();
+        dirs.sort();
+        for dir in dirs {
+            if dir.join(".cmd").symlink_metadata().is_ok() {
+                found.push(dir.to_owned());
+            }
+            if !is_git_root(&dir) {
+                descend(&dir, depth + 1, found);
+            }
+        }
+    }
+    let current_dir = std::env::current_dir().expect("unable to retrieve current directory");
+    let mut ancestors = vec![];
+    for dir in current_dir.ancestors() {
+        ancestors.push(dir);
+        if is_git_root(dir) {
+            break;
+        }
+    }
+    let mut found: Vec<PathBuf> = ancestors.into_iter()
+        .filter(|dir| dir.join(".cmd").symlink_metadata().is_ok())
+        .map(Path::to_path_buf)
+        .collect();
+    found.reverse();
+    descend(&current_dir, 0, &mut found);
+    found
+}
+
+const MAX_ALIAS_LENGTH: usize = 128;
+
+/// Why the alias cannot be the name of a subcommand, if it cannot.
+pub fn alias_problem(alias: &str) -> Option<String> {
+    if alias.is_empty() {
+        Some("it is empty".to_string())
+    } else if alias.starts_with('-') {
+        Some("it starts with a dash".to_string())
+    } else if alias.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        Some("it contains whitespace or control characters".to_string())
+    } else if alias.chars().count() > MAX_ALIAS_LENGTH {
+        Some(format!("it is longer than {MAX_ALIAS_LENGTH} characters"))
+    } else {
+        None
+    }
+}
+
+/// Problems of the index of the scope, and warnings which do not fail the validation.
+fn validate(scope: &Scope) -> (Vec<String>, Vec<String>) {
+    let (mut problems, mut warnings) = (vec![], vec![]);
+    let path = match find_index(&scope.path) {
+        Ok(Some(path)) => path,
+        Ok(None) => {
+            problems.push("there is no index file in .cmd".to_string());
+            return (problems, warnings);
+        },
+        Err(e) => {
+            problems.push(e.to_string());
+            return (problems, warnings);
+        },
+    };
+    let commands = match load_from_file(&path) {
+        Ok(commands) => commands,
+        Err(e) => {
+            problems.push(format!("{} cannot be parsed: {e}", path_to_str(&path)));
+            return (problems, warnings);
+        },
+    };
+    for (i, command) in commands.iter().enumerate() {
+        let alias = &command.alias;
+        if commands[..i].iter().any(|c| c.alias == *alias) {
+            problems.push(format!("alias '{alias}' is used more than once"));
+        }
+        if let Some(problem) = alias_problem(alias) {
+            problems.push(format!("alias '{alias}' cannot be used as a command because {problem}"));
+        } else if is_builtin(alias) {
+            problems.push(format!("alias '{alias}' is shadowed by the builtin command"));
+        }
+        let script = normalize(&scope.path.join(&command.rel_path));
+        if !script.starts_with(&scope.path) {
+            // absolute paths out of the scope come from `add --path`, relative ones climbing out are mistakes
+            if Path::new(&command.rel_path).is_absolute() {
+                warnings.push(format!("script of '{alias}' is outside of the scope: {}", command.rel_path));
+            } else {
+                problems.push(format!("rel_path of '{alias}' leads outside of the scope: {}", command.rel_path));
+            }
+        }
+    }
+    (problems, warnings)
+}
+
+/// The global scope if it has an index, and every local one around the current directory.
+fn all_scopes(global: &Scope) -> Vec<Scope> {
+    let mut scopes = vec![];
+    if find_index(&global.path).map_or(true, |index| index.is_some()) {
+        scopes.push(global.to_owned());
+    }
+    scopes.extend(local_scopes().into_iter()
+        .filter(|path| *path != global.path)
+        .map(|path| Scope{kind: ScopeKind::LOCAL, path}));
+    scopes
+}
+
+/// Prints the result of checking `name` and returns whether it passed.
+fn report(name: &str, problems: &[String], warnings: &[String]) -> bool {
+    let status = if problems.is_empty() { "ok".to_string() } else { format!("{} problem(s)", problems.len()) };
+    println!("{name}: {status}");
+    for problem in problems {
+        println!("  ERR: {problem}");
+    }
+    for warning in warnings {
+        println!("  WARN: {warning}");
+    }
+    problems.is_empty()
+}
+
+/// Validates the index of the global scope and of every local one around the current directory.
+pub fn cmd_validate_all(global: &Scope) {
+    let scopes = all_scopes(global);
+    let mut failed = 0;
+    for scope in &scopes {
+        let (problems, warnings) = validate(scope);
+        if !report(&format!("{} scope {}", scope.kind.name(), path_to_str(&scope.path)), &problems, &warnings) {
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        println!("ERR: {failed} of {} scopes are invalid", scopes.len());
+        std::process::exit(1);
+    }
+}
+
+/// Message of a panic caught by `catch_unwind`.
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload.downcast_ref::<String>().cloned()
+        .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// Checks everything a run loads: the indexes of all scopes around together with their script
+/// files, and the commands of the active scopes, which have to be unique and accepted by clap.
+pub fn cmd_self_check(global: &Scope, groups: &[CmdGroup]) {
+    let mut failed = 0;
+    for scope in all_scopes(global) {
+        let (mut problems, warnings) = validate(&scope);
+        if let Ok(commands) = find_index(&scope.path).and_then(|path| load_from_file(&path.unwrap_or_default())) {
+            problems.extend(commands.iter()
+                .filter(|c| !scope.path.join(&c.rel_path).is_file())
+                .map(|c| format!("script of '{}' is missing: {}", c.alias, c.rel_path)));
+        }
+        if !report(&format!("{} scope {}", scope.kind.name(), path_to_str(&scope.path)), &problems, &warnings) {
+            failed += 1;
+        }
+    }
+    let mut problems = vec![];
+    let commands: Vec<_> = groups.iter().flat_map(|g| &g.commands).collect();
+    for (i, command) in commands.iter().enumerate() {
+        if let Some(other) = commands[..i].iter().find(|c| c.alias == command.alias && c.scope != command.scope) {
+            problems.push(format!("alias '{}' is defined in both the {} and the {} scope", command.alias, other.scope.kind.name(), command.scope.kind.name()));
+        }
+    }
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let built = std::panic::catch_unwind(|| cli_with_scripts(groups).build());
+    std::panic::set_hook(hook);
+    if let Err(payload) = built {
+        problems.push(format!("the scripts cannot be turned into commands: {}", panic_message(payload.as_ref())));
+    }
+    if !report(&format!("commands of the active scopes ({})", commands.len()), &problems, &[]) {
+        failed += 1;
+    }
+    if failed > 0 {
+        println!("ERR: the self-check failed in {failed} place(s)");
+        std::process::exit(1);
+    }
+}
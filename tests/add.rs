@@ -0,0 +1,136 @@
+mod common;
+
+use common::{stdout, Fixture};
+
+#[test]
+fn interactive_add_asks_until_the_alias_is_valid() {
+    let fixture = Fixture::with_scope("add-interactive");
+    fixture.add("build", "make");
+    // ctrl-u clears the pre-filled shell
+    let answers = ["list", "build", "ship", "Ship the release", "ci, release", "\x15bash", "n"];
+    let output = fixture.in_terminal(&["add", "--interactive"], &answers);
+    let transcript = stdout(&output);
+    assert!(output.status.success(), "{transcript}");
+    assert!(transcript.contains("list is a builtin command, choose another alias"), "{transcript}");
+    assert!(transcript.contains("build already exists, choose another alias"), "{transcript}");
+    let entry = fixture.entry("ship").unwrap();
+    assert_eq!(entry["description"], "Ship the release");
+    assert_eq!(entry["tags"], serde_json::json!(["ci", "release"]));
+    assert!(std::fs::read_to_string(fixture.script_path("ship")).unwrap().starts_with("#!/usr/bin/env bash\n"));
+}
+
+#[test]
+fn interactive_add_without_terminal_fails() {
+    let fixture = Fixture::with_scope("add-interactive-no-terminal");
+    let output = fixture.run(&["add", "--interactive"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("ERR: --interactive needs a terminal, pass the ALIAS and options instead"));
+    assert_eq!(fixture.index(), serde_json::json!([]));
+}
+
+/// Directory with a `curl` which prints the script, or fails for URLs containing `missing`.
+fn curl_stub(fixture: &Fixture) -> String {
+    let bin_dir = fixture.root.join("bin");
+    std::fs::create_dir_all(&bin_dir).unwrap();
+    let curl = bin_dir.join("curl");
+    std::fs::write(&curl, "#!/bin/sh\ncase \"$2\" in *missing*) exit 22 ;; esac\nprintf '#!/bin/sh\\necho fetched\\n'\n").unwrap();
+    std::fs::set_permissions(&curl, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+    format!("{}:{}", bin_dir.display(), std::env::var("PATH").unwrap_or_default())
+}
+
+#[test]
+fn from_url_saves_the_download_only_with_trust() {
+    let fixture = Fixture::with_scope("add-from-url");
+    let path = curl_stub(&fixture);
+    let add = |args: &[&str]| fixture.command(args).env("PATH", &path).output().unwrap();
+    let output = add(&["add", "remote", "--from-url", "https://example.com/remote.sh"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("    echo fetched\nERR: review the script and run again with --trust to add it"));
+    assert!(fixture.entry("remote").is_none());
+    let output = add(&["add", "remote", "--from-url", "https://example.com/remote.sh", "--trust"]);
+    assert!(output.status.success(), "{}", common::describe(&output));
+    assert_eq!(std::fs::read_to_string(fixture.script_path("remote")).unwrap(), "#!/bin/sh\necho fetched\n");
+    assert_eq!(fixture.ok(&["remote"]), "fetched\n");
+}
+
+#[test]
+fn failed_download_adds_nothing() {
+    let fixture = Fixture::with_scope("add-from-url-failed");
+    let path = curl_stub(&fixture);
+    let output = fixture.command(&["add", "remote", "--from-url", "https://example.com/missing.sh", "--trust"]).env("PATH", &path).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("ERR: unable to download https://example.com/missing.sh: curl failed"));
+    assert_eq!(fixture.index(), serde_json::json!([]));
+    assert_eq!(std::fs::read_dir(fixture.work().join(".cmd/scripts")).unwrap().count(), 0);
+}
+
+#[test]
+fn alias_with_a_slash_gets_a_safe_file_name() {
+    let fixture = Fixture::with_scope("add-slash");
+    fixture.add("db/migrate", "echo migrated");
+    fixture.ok(&["add", "db_migrate"]);
+    assert_eq!(fixture.entry("db/migrate").unwrap()["rel_path"], "./.cmd/scripts/db_migrate.sh");
+    assert_eq!(fixture.entry("db_migrate").unwrap()["rel_path"], "./.cmd/scripts/db_migrate-2.sh");
+    assert_eq!(fixture.ok(&["db/migrate"]), "migrated\n");
+}
+
+#[test]
+fn alias_which_is_no_command_name_is_refused() {
+    let fixture = Fixture::with_scope("add-bad-alias");
+    for (args, problem) in [
+        (&["add", ""][..], "because it is empty"),
+        (&["add", "two words"][..], "because it contains whitespace"),
+        (&["add", "--", "-x"][..], "because it starts with a dash"),
+    ] {
+        let output = fixture.run(args);
+        assert!(!output.status.success(), "{args:?}");
+        assert!(common::stderr(&output).contains(problem), "{args:?}: {}", common::describe(&output));
+    }
+    assert_eq!(fixture.index(), serde_json::json!([]));
+    assert_eq!(std::fs::read_dir(fixture.work().join(".cmd/scripts")).unwrap().count(), 0);
+}
+
+#[test]
+fn too_long_alias_is_refused() {
+    let fixture = Fixture::with_scope("add-long");
+    let alias = "a".repeat(129);
+    let output = fixture.run(&["add", &alias]);
+    assert!(!output.status.success());
+    assert!(common::stderr(&output).contains("because it is longer than 128 characters"));
+    assert_eq!(fixture.index(), serde_json::json!([]));
+    fixture.ok(&["add", &alias[..128]]);
+}
+
+#[test]
+fn no_stub_registers_an_existing_script() {
+    let fixture = Fixture::with_scope("add-no-stub");
+    std::fs::create_dir_all(fixture.work().join("tools")).unwrap();
+    std::fs::write(fixture.work().join("Makefile"), "all:\n").unwrap();
+    let script = fixture.work().join("tools/build.sh");
+    std::fs::write(&script, "#!/bin/sh\necho building \"$@\"\n").unwrap();
+    std::fs::set_permissions(&script, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+    let editor = fixture.root.join("no-editor");
+    let output = fixture.command(&["add", "build", "--no-stub", "--path", "tools/build.sh"]).env("EDITOR", &editor).output().unwrap();
+    assert!(output.status.success(), "{}", common::describe(&output));
+    assert_eq!(std::fs::canonicalize(fixture.script_path("build")).unwrap(), std::fs::canonicalize(&script).unwrap());
+    assert_eq!(std::fs::read_dir(fixture.work().join(".cmd/scripts")).unwrap().count(), 0);
+    assert_eq!(fixture.ok(&["build", "all"]), "building all\n");
+    let output = fixture.ok(&["add", "later", "--no-stub", "--path", "tools/later.sh"]);
+    assert!(output.contains("WARN"), "{output}");
+    assert!(fixture.entry("later").is_some());
+}
+
+#[test]
+fn template_placeholders_are_substituted() {
+    let fixture = Fixture::with_scope("add-template");
+    let templates = fixture.work().join(".cmd/templates");
+    std::fs::create_dir_all(&templates).unwrap();
+    std::fs::write(templates.join("service"), "#!/bin/sh\n# {{alias}}: {{description}}\nsystemctl restart {{service}} {{unknown}}\n").unwrap();
+    fixture.ok(&["add", "restart-db", "Restart the database", "--template", "service", "--template-var", "service=postgres"]);
+    let script = std::fs::read_to_string(fixture.script_path("restart-db")).unwrap();
+    assert_eq!(script, "#!/bin/sh\n# restart-db: Restart the database\nsystemctl restart postgres {{unknown}}\n");
+    let output = fixture.run(&["add", "restart-web", "--template", "service", "--template-var", "service=nginx", "--strict"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("ERR: unable to create restart-web, the template has placeholders without a value: unknown"));
+    assert!(fixture.entry("restart-web").is_none());
+}
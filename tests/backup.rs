@@ -0,0 +1,20 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn restore_recovers_the_backed_up_index() {
+    let fixture = Fixture::with_scope("backup");
+    fixture.add_with("build", &["Build it", "--tag", "ci"], "make");
+    fixture.add_with("deploy", &["--depends", "build"], "./deploy");
+    let original = std::fs::read_to_string(fixture.work().join(".cmd/index.json")).unwrap();
+    assert!(fixture.ok(&["backup"]).starts_with("INFO: backup "));
+    let listed = fixture.ok(&["backup", "--list"]);
+    let name = listed.split_whitespace().next().unwrap().to_string();
+    assert!(listed.trim_end().ends_with("index"), "{listed}");
+    std::fs::write(fixture.work().join(".cmd/index.json"), "[]").unwrap();
+    assert_eq!(fixture.ok(&["list", "--plain"]), "");
+    fixture.ok(&["restore", &name]);
+    assert_eq!(std::fs::read_to_string(fixture.work().join(".cmd/index.json")).unwrap(), original);
+    assert_eq!(fixture.ok(&["list", "--plain"]), "build\ndeploy\n");
+}
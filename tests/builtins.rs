@@ -0,0 +1,48 @@
+mod common;
+
+use common::{stderr, stdout, Fixture};
+
+#[test]
+fn dashed_and_plain_builtins_behave_the_same() {
+    let plain = Fixture::new("builtins-plain");
+    let dashed = Fixture::new("builtins-dashed");
+    assert_eq!(plain.ok(&["init"]), dashed.ok(&["--init"]));
+    assert_eq!(plain.index(), dashed.index());
+    plain.ok(&["add", "build"]);
+    dashed.ok(&["--add", "build"]);
+    assert_eq!(plain.index(), dashed.index());
+    assert_eq!(plain.ok(&["list", "--plain"]), dashed.ok(&["--list", "--plain"]));
+}
+
+#[test]
+fn script_shadowed_by_builtin_is_ignored_with_warning() {
+    let fixture = Fixture::with_scope("builtins-shadowed");
+    fixture.add("build", "echo built");
+    let index = r#"[
+        {"alias": "build", "rel_path": "./.cmd/scripts/build.sh", "description": ""},
+        {"alias": "diff", "rel_path": "./.cmd/scripts/build.sh", "description": ""},
+        {"alias": "build", "rel_path": "./.cmd/scripts/build.sh", "description": ""}
+    ]"#;
+    std::fs::write(fixture.work().join(".cmd/index.json"), index).unwrap();
+    let output = fixture.run(&["build"]);
+    assert!(output.status.success(), "{}", common::describe(&output));
+    assert_eq!(stdout(&output), "built\n");
+    assert!(stderr(&output).contains("WARN: script 'diff' of the local scope is shadowed by the builtin command, run it by `shell-scriptman run diff`"));
+    assert!(stderr(&output).contains("WARN: ignoring script 'build' of the local scope, its alias is used by another script"));
+    assert_eq!(fixture.ok(&["run", "diff", "--stat"]), "built\n");
+    fixture.ok(&["rename", "diff", "show-diff"]);
+    let output = fixture.run(&["list", "--plain"]);
+    assert!(!stderr(&output).contains("'diff'"));
+}
+
+#[test]
+fn run_passes_the_arguments_and_the_exit_code() {
+    let fixture = Fixture::with_scope("builtins-run");
+    fixture.add("show", "echo \"$@\"\nexit 3");
+    let output = fixture.run(&["run", "show", "-x", "two words"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(stdout(&output).starts_with("-x two words\n"), "{}", common::describe(&output));
+    let output = fixture.run(&["run", "missing"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "missing is an unknown command\n");
+}
@@ -0,0 +1,190 @@
+//! Runs the binary in throwaway directories, isolated from the scripts and settings of the user.
+#![allow(dead_code)]
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+pub const BIN: &str = env!("CARGO_BIN_EXE_shell-scriptman");
+
+/// The tests which need a terminal get it from util-linux `script`.
+pub const NO_SCRIPT: &str = "unable to run `script`, the tests need util-linux installed for a terminal";
+
+/// A working directory with its own home, config, and global scope, removed when dropped.
+pub struct Fixture {
+    pub root: PathBuf,
+}
+
+impl Fixture {
+    /// The `name` has to be unique among the tests, the directories are derived from it.
+    pub fn new(name: &str) -> Fixture {
+        let root = std::env::temp_dir().join(format!("shell-scriptman-test-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let fixture = Fixture{root};
+        for dir in [fixture.work(), fixture.global(), fixture.config_dir()] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        std::fs::write(fixture.config_dir().join("config.toml"), format!("global-scope = {:?}\n", fixture.global())).unwrap();
+        fixture
+    }
+
+    /// Like `new`, with the local scope initialized in the working directory.
+    pub fn with_scope(name: &str) -> Fixture {
+        let fixture = Fixture::new(name);
+        fixture.ok(&["init"]);
+        fixture
+    }
+
+    pub fn work(&self) -> PathBuf {
+        self.root.join("work")
+    }
+
+    pub fn home(&self) -> PathBuf {
+        self.root.join("home")
+    }
+
+    pub fn global(&self) -> PathBuf {
+        self.root.join("global")
+    }
+
+    pub fn config_dir(&self) -> PathBuf {
+        self.home().join(".config/shell-scriptman")
+    }
+
+    /// The binary run in the working directory, with `true` as the editor.
+    pub fn command(&self, args: &[&str]) -> Command {
+        self.command_in(&self.work(), args)
+    }
+
+    pub fn command_in(&self, dir: &Path, args: &[&str]) -> Command {
+        let mut command = self.program(BIN, args);
+        command.current_dir(dir);
+        command
+    }
+
+    /// Any program run with the environment of the binary, e.g. a wrapper of it.
+    pub fn program(&self, program: impl AsRef<std::ffi::OsStr>, args: &[&str]) -> Command {
+        let mut command = Command::new(program);
+        command.args(args)
+            .current_dir(self.work())
+            .env("HOME", self.home())
+            .env("XDG_CONFIG_HOME", self.home().join(".config"))
+            .env("XDG_DATA_HOME", self.home().join(".local/share"))
+            .env("XDG_CACHE_HOME", self.home().join(".cache"))
+            .env("XDG_STATE_HOME", self.home().join(".local/state"))
+            .env("EDITOR", "true")
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .env("GIT_AUTHOR_NAME", "test")
+            .env("GIT_AUTHOR_EMAIL", "test@example.com")
+            .env("GIT_COMMITTER_NAME", "test")
+            .env("GIT_COMMITTER_EMAIL", "test@example.com");
+        command
+    }
+
+    /// Runs the binary in a terminal of util-linux `script` and types the answers, each ended by enter,
+    /// once the previous prompt has had time to appear.
+    pub fn in_terminal(&self, args: &[&str], answers: &[&str]) -> Output {
+        let mut line = BIN.to_string();
+        args.iter().for_each(|arg| line.push_str(&format!(" '{arg}'")));
+        let mut child = self.program("script", &["-qec", &line, "/dev/null"])
+            .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped())
+            .spawn().expect(NO_SCRIPT);
+        let mut keys = child.stdin.take().unwrap();
+        for answer in answers {
+            std::thread::sleep(std::time::Duration::from_millis(300));
+            keys.write_all(format!("{answer}\r").as_bytes()).unwrap();
+        }
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        drop(keys);
+        child.wait_with_output().unwrap()
+    }
+
+    pub fn run(&self, args: &[&str]) -> Output {
+        self.command(args).output().unwrap()
+    }
+
+    /// Runs the binary, which has to succeed, and returns its stdout.
+    pub fn ok(&self, args: &[&str]) -> String {
+        let output = self.run(args);
+        assert!(output.status.success(), "{args:?} failed: {}", describe(&output));
+        stdout(&output)
+    }
+
+    /// Adds the script to the local scope and replaces its body, returns the path of its file.
+    pub fn add(&self, alias: &str, body: &str) -> PathBuf {
+        self.add_with(alias, &[], body)
+    }
+
+    pub fn add_with(&self, alias: &str, options: &[&str], body: &str) -> PathBuf {
+        let mut args = vec!["add", alias];
+        args.extend(options);
+        self.ok(&args);
+        let path = self.script_path(alias);
+        std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+        path
+    }
+
+    /// File of the script in the local index.
+    pub fn script_path(&self, alias: &str) -> PathBuf {
+        let command = self.entry(alias).unwrap_or_else(|| panic!("{alias} is not in the index"));
+        self.work().join(command["rel_path"].as_str().unwrap())
+    }
+
+    /// The local `index.json` parsed.
+    pub fn index(&self) -> serde_json::Value {
+        read_index(&self.work())
+    }
+
+    pub fn entry(&self, alias: &str) -> Option<serde_json::Value> {
+        self.index().as_array().unwrap().iter().find(|c| c["alias"] == alias).cloned()
+    }
+
+    /// File where the runs of the scripts are recorded.
+    pub fn runs_path(&self) -> PathBuf {
+        self.home().join(".local/state/shell-scriptman/runs.json")
+    }
+
+    /// Recorded runs of the local script, none if it was not run.
+    pub fn runs(&self, alias: &str) -> Option<serde_json::Value> {
+        let rel_path = self.entry(alias)?["rel_path"].as_str()?.to_string();
+        let data = std::fs::read_to_string(self.runs_path()).ok()?;
+        let runs: serde_json::Value = serde_json::from_str(&data).unwrap();
+        runs.get(self.work().to_str().unwrap())?.get(rel_path).cloned()
+    }
+
+    pub fn git(&self, args: &[&str]) -> String {
+        let output = Command::new("git").args(args).current_dir(self.work())
+            .env("HOME", self.home())
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .env("GIT_AUTHOR_NAME", "test")
+            .env("GIT_AUTHOR_EMAIL", "test@example.com")
+            .env("GIT_COMMITTER_NAME", "test")
+            .env("GIT_COMMITTER_EMAIL", "test@example.com")
+            .output().unwrap();
+        assert!(output.status.success(), "git {args:?} failed: {}", describe(&output));
+        stdout(&output)
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
+
+pub fn read_index(scope: &Path) -> serde_json::Value {
+    let data = std::fs::read_to_string(scope.join(".cmd/index.json")).unwrap();
+    serde_json::from_str(&data).unwrap()
+}
+
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+pub fn describe(output: &Output) -> String {
+    format!("{}\nstdout:\n{}\nstderr:\n{}", output.status, stdout(output), stderr(output))
+}
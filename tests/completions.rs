@@ -0,0 +1,236 @@
+mod common;
+
+use common::{stdout, Fixture};
+
+#[test]
+fn dynamic_bash_lists_the_scripts_when_completing() {
+    let fixture = Fixture::new("dynamic-bash");
+    let script = fixture.ok(&["completions", "bash", "--dynamic"]);
+    assert!(script.contains(r#"shell-scriptman "${scope[@]}" --list --plain"#), "{script}");
+    assert!(script.contains("complete -o default -F _shell_scriptman_dynamic shell-scriptman"), "{script}");
+}
+
+#[test]
+fn zsh_completion_offers_the_undashed_builtins() {
+    let fixture = Fixture::with_scope("zsh-builtins");
+    fixture.add("build", "make");
+    let script = fixture.ok(&["completions", "zsh"]);
+    assert!(script.contains("'init:Setup local scope in the current directory'"), "{script}");
+    assert!(script.contains("'add:Create script and open it in the \\$EDITOR'"), "{script}");
+    assert!(script.contains("'build:'"), "{script}");
+    assert!(!script.contains("'--init:") && !script.contains("'-a:"), "{script}");
+    // the syntax is checked where zsh is installed
+    let path = fixture.root.join("completion.zsh");
+    std::fs::write(&path, &script).unwrap();
+    if let Ok(output) = std::process::Command::new("zsh").arg("-n").arg(&path).output() {
+        assert!(output.status.success(), "{}", common::describe(&output));
+    }
+}
+
+#[test]
+fn completion_for_a_script_completes_its_dir_argument() {
+    let fixture = Fixture::with_scope("completion-for");
+    fixture.add_with("stash-dir", &["--arg-spec", "dir,any"], "cp -r \"$1\" /backup");
+    let fragment = fixture.ok(&["completions", "bash", "--for", "stash-dir"]);
+    assert!(fragment.contains("_shell_scriptman_stash_dir()"), "{fragment}");
+    assert!(fragment.contains("1) COMPREPLY=($(compgen -d -- \"$cur\")) ;;"), "{fragment}");
+    assert!(!fragment.contains("2)"), "{fragment}");
+    let fragment = fixture.ok(&["completions", "zsh", "--for", "stash-dir"]);
+    assert!(fragment.contains("':arg1:_files -/'"), "{fragment}");
+    let output = fixture.run(&["completions", "fish", "--for", "stash-dir"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("ERR: completion of a single script is supported only for bash and zsh, not fish"));
+}
+
+#[test]
+fn completions_use_the_name_the_binary_is_run_as() {
+    let fixture = Fixture::new("completions-renamed");
+    let output = std::os::unix::process::CommandExt::arg0(&mut fixture.command(&["completions", "bash"]), "sm").output().unwrap();
+    assert!(output.status.success(), "{}", common::describe(&output));
+    let script = stdout(&output);
+    assert!(script.contains("complete -F _sm -o bashdefault -o default sm"), "{script}");
+    // the paths of the fixture contain the crate name, the registered names must not
+    assert!(!script.contains("_shell_scriptman"), "{script}");
+    assert!(script.lines().filter(|l| l.trim_start().starts_with("complete ")).all(|l| l.ends_with(" sm")), "{script}");
+}
+
+#[test]
+fn complete_commands_prints_alias_and_description_separated_by_tab() {
+    let fixture = Fixture::with_scope("complete-commands");
+    fixture.add_with("build", &["Build the project"], "make");
+    fixture.add("deploy", "./deploy");
+    assert_eq!(fixture.ok(&["complete-commands"]), "build\tBuild the project\ndeploy\t\n");
+    assert_eq!(fixture.ok(&["--complete-commands"]), "build\tBuild the project\ndeploy\t\n");
+}
+
+#[test]
+fn stale_completions_hint_appears_only_when_they_are_installed() {
+    let fixture = Fixture::with_scope("completions-hint");
+    let hint = "INFO: installed bash completions are out of date, pass --update-completions to regenerate them";
+    assert!(!fixture.ok(&["add", "build"]).contains(hint));
+    fixture.ok(&["completions", "bash", "--static", "--install"]);
+    assert!(fixture.ok(&["add", "deploy"]).contains(hint));
+    assert!(fixture.ok(&["rename", "deploy", "ship"]).contains(hint));
+    assert!(!fixture.ok(&["--quiet", "remove", "ship"]).contains(hint));
+    let installed = fixture.home().join(".local/share/bash-completion/completions/shell-scriptman");
+    assert!(!std::fs::read_to_string(&installed).unwrap().contains("zebra"));
+    let output = fixture.ok(&["--update-completions", "add", "zebra"]);
+    assert!(!output.contains(hint), "{output}");
+    assert!(std::fs::read_to_string(&installed).unwrap().contains("zebra"));
+}
+
+#[test]
+fn merge_keeps_a_single_block_in_the_file() {
+    let fixture = Fixture::with_scope("completions-merge");
+    let bashrc = fixture.home().join(".bashrc");
+    std::fs::create_dir_all(fixture.home()).unwrap();
+    std::fs::write(&bashrc, "export EDITOR=vim\n").unwrap();
+    fixture.add("first", "true");
+    fixture.ok(&["completions", "bash", "--static", "--merge", bashrc.to_str().unwrap()]);
+    std::fs::write(&bashrc, std::fs::read_to_string(&bashrc).unwrap() + "alias ll='ls -l'\n").unwrap();
+    fixture.add("second", "true");
+    fixture.ok(&["completions", "bash", "--static", "--merge", bashrc.to_str().unwrap()]);
+    let content = std::fs::read_to_string(&bashrc).unwrap();
+    assert_eq!(content.matches("# >>> shell-scriptman >>>").count(), 1, "{content}");
+    assert_eq!(content.matches("# <<< shell-scriptman <<<").count(), 1, "{content}");
+    assert!(content.starts_with("export EDITOR=vim\n"), "{content}");
+    assert!(content.contains("alias ll='ls -l'\n"), "{content}");
+    assert!(content.contains("second"), "{content}");
+}
+
+#[test]
+fn dynamic_bash_offers_only_the_scripts_of_the_forced_scope() {
+    let fixture = Fixture::with_scope("dynamic-bash-scope");
+    fixture.add("local-one", "true");
+    fixture.ok(&["-g", "add", "global-one"]);
+    let script = fixture.root.join("completion.bash");
+    std::fs::write(&script, fixture.ok(&["completions", "bash", "--dynamic"])).unwrap();
+    let complete = |words: &str| {
+        let line = format!(". {script:?}; COMP_WORDS=({words} ''); COMP_CWORD=${{#COMP_WORDS[@]}}; COMP_CWORD=$((COMP_CWORD - 1)); \
+            _shell_scriptman_dynamic; printf '%s\\n' \"${{COMPREPLY[@]}}\" | grep -- -one");
+        let bin_dir = std::path::Path::new(common::BIN).parent().unwrap();
+        let path = format!("{}:{}", bin_dir.display(), std::env::var("PATH").unwrap_or_default());
+        stdout(&fixture.program("bash", &["-c", &line]).env("PATH", path).output().unwrap())
+    };
+    assert_eq!(complete("shell-scriptman -g"), "global-one\n");
+    assert_eq!(complete("shell-scriptman -l"), "local-one\n");
+    assert_eq!(complete("shell-scriptman"), "global-one\nlocal-one\n");
+}
+
+#[test]
+fn dynamic_bash_reuses_the_aliases_until_an_index_changes() {
+    let fixture = Fixture::with_scope("dynamic-bash-cache");
+    fixture.add("first", "true");
+    // counts how many times the completion asks the binary for the aliases
+    let bin_dir = fixture.root.join("bin");
+    std::fs::create_dir_all(&bin_dir).unwrap();
+    let log = fixture.root.join("calls.log");
+    std::fs::write(bin_dir.join("shell-scriptman"), format!("#!/bin/bash\necho >> {log:?}\nexec -a shell-scriptman {:?} \"$@\"\n", common::BIN)).unwrap();
+    std::fs::set_permissions(bin_dir.join("shell-scriptman"), std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+    let script = fixture.root.join("completion.bash");
+    std::fs::write(&script, fixture.ok(&["completions", "bash", "--dynamic"])).unwrap();
+    let complete = || {
+        let line = format!(". {script:?}; COMP_WORDS=(shell-scriptman ''); COMP_CWORD=1; _shell_scriptman_dynamic; printf '%s\\n' \"${{COMPREPLY[@]}}\"");
+        let path = format!("{}:{}", bin_dir.display(), std::env::var("PATH").unwrap_or_default());
+        stdout(&fixture.program("bash", &["-c", &line]).env("PATH", path).output().unwrap())
+    };
+    let calls = || std::fs::read_to_string(&log).unwrap_or_default().lines().count();
+    assert!(complete().contains("first\n"));
+    let after_first = calls();
+    assert!(after_first > 0);
+    assert!(complete().contains("first\n"));
+    assert_eq!(calls(), after_first);
+    // the cache is older than the changed index from now on
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    fixture.add("second", "true");
+    assert!(complete().contains("second\n"));
+    assert!(calls() > after_first);
+}
+
+#[test]
+fn print_path_reports_the_install_location_without_writing() {
+    let fixture = Fixture::new("completions-print-path");
+    let expected = fixture.home().join(".local/share/bash-completion/completions/shell-scriptman");
+    assert_eq!(fixture.ok(&["completions", "bash", "--print-path"]), format!("{}\n", expected.display()));
+    assert!(!expected.exists());
+    assert_eq!(fixture.ok(&["completions", "zsh", "--print-path"]), format!("{}\n", fixture.home().join(".zfunc/_shell-scriptman").display()));
+}
+
+#[test]
+fn uninstall_removes_the_installed_file_and_the_merged_block() {
+    let fixture = Fixture::with_scope("completions-uninstall");
+    let installed = fixture.home().join(".local/share/bash-completion/completions/shell-scriptman");
+    fixture.ok(&["completions", "bash", "--static", "--install"]);
+    assert!(installed.is_file());
+    let output = fixture.ok(&["completions", "bash", "--uninstall"]);
+    assert!(output.contains(&installed.display().to_string()), "{output}");
+    assert!(!installed.exists());
+    assert!(fixture.ok(&["completions", "bash", "--uninstall"]).contains("INFO: no installed bash completions were found"));
+    let bashrc = fixture.home().join(".bashrc");
+    std::fs::write(&bashrc, "export EDITOR=vim\n").unwrap();
+    fixture.ok(&["completions", "bash", "--static", "--merge", bashrc.to_str().unwrap()]);
+    std::fs::write(&bashrc, std::fs::read_to_string(&bashrc).unwrap() + "alias ll='ls -l'\n").unwrap();
+    fixture.ok(&["completions", "bash", "--uninstall"]);
+    assert_eq!(std::fs::read_to_string(&bashrc).unwrap(), "export EDITOR=vim\nalias ll='ls -l'\n");
+}
+
+#[test]
+fn listed_shells_are_those_the_parser_accepts() {
+    let fixture = Fixture::new("completions-shells");
+    let shells = fixture.ok(&["completions", "--shells"]);
+    let output = fixture.run(&["completions", "no-such-shell"]);
+    let error = common::stderr(&output);
+    let accepted = error.split("[possible values: ").nth(1).and_then(|rest| rest.split(']').next()).expect(&error);
+    assert_eq!(shells.lines().collect::<Vec<_>>(), accepted.split(", ").collect::<Vec<_>>());
+    for shell in shells.lines().filter(|s| *s != "powershell" && *s != "elvish") {
+        fixture.ok(&["completions", shell]);
+    }
+}
+
+#[test]
+fn dynamic_fish_and_zsh_take_the_descriptions_from_the_helper() {
+    let fixture = Fixture::new("dynamic-descriptions");
+    let fish = fixture.ok(&["completions", "fish", "--dynamic"]);
+    assert!(fish.contains("shell-scriptman (commandline -opc | string match -r -- '^(-l|--local|-g|--global)$') complete-commands 2>/dev/null"), "{fish}");
+    assert!(fish.contains("complete -c shell-scriptman -n \""), "{fish}");
+    let zsh = fixture.ok(&["completions", "zsh", "--dynamic"]);
+    assert!(zsh.contains("complete-commands"), "{zsh}");
+}
+
+#[test]
+fn default_bash_completion_asks_for_the_aliases_after_the_builtins() {
+    let fixture = Fixture::with_scope("bash-default");
+    fixture.add("build", "true");
+    // installed under a name without a dash, clap_complete mismatches the subcommands of dashed names
+    let bin_dir = fixture.root.join("bin");
+    std::fs::create_dir_all(&bin_dir).unwrap();
+    std::os::unix::fs::symlink(common::BIN, bin_dir.join("sm")).unwrap();
+    let output = fixture.program(bin_dir.join("sm"), &["completions", "bash"]).output().unwrap();
+    let script = stdout(&output);
+    assert!(script.contains(r#"sm "${scope[@]}" --list --plain"#), "{script}");
+    assert!(!script.contains("build"), "{script}");
+    let path = fixture.root.join("completion.bash");
+    std::fs::write(&path, &script).unwrap();
+    // called like bash does, with the command, the current word, and the previous word
+    let complete = |words: &str| {
+        let line = format!(". {path:?}; COMP_WORDS=({words}); COMP_CWORD=$((${{#COMP_WORDS[@]}} - 1)); \
+            _sm_dynamic sm \"${{COMP_WORDS[COMP_CWORD]}}\" \"${{COMP_WORDS[COMP_CWORD-1]}}\"; printf '%s\\n' \"${{COMPREPLY[@]}}\"");
+        let search = format!("{}:{}", bin_dir.display(), std::env::var("PATH").unwrap_or_default());
+        stdout(&fixture.program("bash", &["-c", &line]).env("PATH", search).output().unwrap())
+    };
+    let first = complete("sm ''");
+    assert!(first.lines().any(|w| w == "build") && first.lines().any(|w| w == "list"), "{first}");
+    assert_eq!(complete("sm build ''"), "\n");
+    let options = complete("sm list --p");
+    assert!(options.lines().any(|w| w == "--plain"), "{options}");
+}
+
+#[test]
+fn dynamic_completions_of_unsupported_shell_fail_cleanly() {
+    let fixture = Fixture::new("dynamic-unsupported");
+    for shell in ["powershell", "elvish"] {
+        let output = fixture.run(&["completions", shell, "--dynamic"]);
+        assert_eq!(output.status.code(), Some(1));
+        assert!(stdout(&output).contains("ERR: dynamic completions are supported only for bash, zsh, and fish"));
+    }
+}
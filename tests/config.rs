@@ -0,0 +1,38 @@
+mod common;
+
+use common::{stderr, Fixture};
+
+#[test]
+fn config_sets_gets_and_lists_the_settings() {
+    let fixture = Fixture::new("config");
+    fixture.ok(&["config", "editor", "nvim"]);
+    assert!(std::fs::read_to_string(fixture.config_dir().join("config.toml")).unwrap().contains("editor = \"nvim\""));
+    assert_eq!(fixture.ok(&["config", "editor"]), "nvim\n");
+    let list = fixture.ok(&["config", "--list"]);
+    assert_eq!(list, format!("editor = nvim\ndefault-scope = \nglobal-scope = {}\n", fixture.global().display()));
+    fixture.ok(&["config", "editor", "--unset"]);
+    let output = fixture.run(&["config", "editor"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(common::stdout(&output), "");
+}
+
+#[test]
+fn config_rejects_an_unknown_key() {
+    let fixture = Fixture::new("config-unknown");
+    let before = std::fs::read_to_string(fixture.config_dir().join("config.toml")).unwrap();
+    let output = fixture.run(&["config", "color", "red"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("invalid value 'color' for '[KEY]'"));
+    assert_eq!(std::fs::read_to_string(fixture.config_dir().join("config.toml")).unwrap(), before);
+}
+
+#[test]
+fn broken_config_is_reported_once_on_stderr() {
+    let fixture = Fixture::with_scope("config-broken");
+    fixture.add("build", "echo built");
+    std::fs::write(fixture.config_dir().join("config.toml"), "colour = 1\n").unwrap();
+    let output = fixture.run(&["build"]);
+    assert!(output.status.success(), "{}", common::describe(&output));
+    assert_eq!(common::stdout(&output), "built\n");
+    assert_eq!(stderr(&output).matches("WARN").count(), 1, "{}", stderr(&output));
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use std::io::Write;
+
+use common::Fixture;
+
+#[test]
+fn sync_descriptions_fills_them_from_the_scripts() {
+    let fixture = Fixture::with_scope("sync-descriptions");
+    fixture.add("build", "# desc: Build the project\nmake");
+    fixture.add("deploy", "# desc: Deploy to production\n./deploy");
+    let output = fixture.ok(&["sync-descriptions"]);
+    assert!(output.contains("INFO: updated 2 description(s)"), "{output}");
+    assert_eq!(fixture.entry("build").unwrap()["description"], "Build the project");
+    assert_eq!(fixture.entry("deploy").unwrap()["description"], "Deploy to production");
+}
+
+#[test]
+fn sync_descriptions_overwrites_only_when_asked() {
+    let fixture = Fixture::with_scope("sync-descriptions-overwrite");
+    fixture.add_with("build", &["Old text"], "# desc: New text");
+    fixture.ok(&["sync-descriptions"]);
+    assert_eq!(fixture.entry("build").unwrap()["description"], "Old text");
+    fixture.ok(&["sync-descriptions", "--overwrite"]);
+    assert_eq!(fixture.entry("build").unwrap()["description"], "New text");
+}
+
+#[test]
+fn sync_descriptions_respects_the_scope() {
+    let fixture = Fixture::with_scope("sync-descriptions-scope");
+    fixture.add("build", "# desc: Local one");
+    fixture.ok(&["-g", "add", "tidy"]);
+    let global_index = common::read_index(&fixture.global());
+    let script = fixture.global().join(global_index[0]["rel_path"].as_str().unwrap());
+    std::fs::write(script, "#!/bin/sh\n# desc: Global one\n").unwrap();
+    fixture.ok(&["-g", "sync-descriptions"]);
+    assert_eq!(common::read_index(&fixture.global())[0]["description"], "Global one");
+    assert_eq!(fixture.entry("build").unwrap()["description"], "");
+}
+
+#[test]
+fn describe_without_text_edits_the_description_in_place() {
+    let fixture = Fixture::with_scope("describe-inline");
+    fixture.add_with("build", &["Old text"], "make");
+    // util-linux `script` gives the line editor a terminal
+    let mut describe = fixture.program("script", &["-qec", &format!("{} describe build", common::BIN), "/dev/null"])
+        .stdin(std::process::Stdio::piped()).stdout(std::process::Stdio::null()).spawn().expect(common::NO_SCRIPT);
+    let mut keys = describe.stdin.take().unwrap();
+    // the keys arrive after the editor took over the terminal, ctrl-u clears the pre-filled text
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    keys.write_all(b"\x15New text\r").unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    drop(keys);
+    assert!(describe.wait().unwrap().success());
+    assert_eq!(fixture.entry("build").unwrap()["description"], "New text");
+}
+
+#[test]
+fn describe_without_text_nor_terminal_opens_the_index_in_the_editor() {
+    let fixture = Fixture::with_scope("describe-no-terminal");
+    fixture.add_with("build", &["Old text"], "make");
+    let output = fixture.command(&["describe", "build"]).env("EDITOR", "echo").output().unwrap();
+    assert!(output.status.success(), "{}", common::describe(&output));
+    assert!(common::stdout(&output).contains(".cmd/index.json"), "{}", common::describe(&output));
+    assert_eq!(fixture.entry("build").unwrap()["description"], "Old text");
+}
@@ -0,0 +1,25 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn diff_shows_the_uncommitted_changes_of_a_script() {
+    let fixture = Fixture::new("diff");
+    fixture.git(&["init", "-q"]);
+    fixture.ok(&["init"]);
+    let path = fixture.add("build", "make");
+    fixture.git(&["add", "."]);
+    fixture.git(&["commit", "-qm", "scripts"]);
+    assert_eq!(fixture.ok(&["diff", "build"]), "");
+    std::fs::write(&path, "#!/bin/sh\nmake all\n").unwrap();
+    let diff = fixture.ok(&["diff", "build"]);
+    assert!(diff.contains("-make\n+make all\n"), "{diff}");
+}
+
+#[test]
+fn diff_outside_of_git_says_so() {
+    let fixture = Fixture::with_scope("diff-no-git");
+    fixture.add("build", "make");
+    let output = fixture.ok(&["diff", "build"]);
+    assert!(output.contains("INFO: the local scope is not inside a git repository"), "{output}");
+}
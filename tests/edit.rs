@@ -0,0 +1,73 @@
+mod common;
+
+use common::{stderr, stdout, Fixture};
+
+#[test]
+fn missing_editor_is_reported_without_panic() {
+    let fixture = Fixture::with_scope("missing-editor");
+    let output = fixture.command(&["add", "foo"]).env("EDITOR", "no-such-editor-xyz").output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("error: could not launch editor 'no-such-editor-xyz' ($EDITOR): not found"), "{}", stderr(&output));
+    assert!(!stderr(&output).contains("panicked"));
+    assert!(stdout(&output).contains("INFO: foo was added with script ./.cmd/scripts/foo.sh"));
+    assert!(fixture.script_path("foo").is_file());
+}
+
+/// Editor which appends its arguments as a line to `argv.log`.
+fn logging_editor(fixture: &Fixture) -> std::path::PathBuf {
+    let editor = fixture.root.join("editor");
+    let log = fixture.root.join("argv.log");
+    std::fs::write(&editor, format!("#!/bin/sh\necho \"$@\" >> {log:?}\n")).unwrap();
+    std::fs::set_permissions(&editor, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+    editor
+}
+
+#[test]
+fn edit_all_opens_the_existing_scripts_at_once() {
+    let fixture = Fixture::with_scope("edit-all");
+    let build = fixture.add("build", "make");
+    let deploy = fixture.add("deploy", "./deploy");
+    let missing = fixture.add("gone", "true");
+    std::fs::remove_file(missing).unwrap();
+    let editor = logging_editor(&fixture);
+    let output = fixture.command(&["edit", "--all"]).env("EDITOR", &editor).output().unwrap();
+    assert!(output.status.success(), "{}", common::describe(&output));
+    assert!(stdout(&output).contains("WARN: skipping gone, its script ./.cmd/scripts/gone.sh does not exist"));
+    let log = std::fs::read_to_string(fixture.root.join("argv.log")).unwrap();
+    assert_eq!(log, format!("{} {}\n", build.display(), deploy.display()));
+}
+
+#[test]
+fn readonly_script_is_edited_only_with_force() {
+    let fixture = Fixture::with_scope("edit-readonly");
+    let path = fixture.add_with("release", &["--readonly"], "true");
+    let mode = std::os::unix::fs::PermissionsExt::mode(&std::fs::metadata(&path).unwrap().permissions());
+    assert_eq!(mode & 0o200, 0);
+    let editor = logging_editor(&fixture);
+    let output = fixture.command(&["edit", "release"]).env("EDITOR", &editor).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("ERR: release is readonly, use --force to edit it anyway or `unlock release`"));
+    assert!(!fixture.root.join("argv.log").exists());
+    let output = fixture.command(&["edit", "release", "--force"]).env("EDITOR", &editor).output().unwrap();
+    assert!(output.status.success(), "{}", common::describe(&output));
+    assert_eq!(std::fs::read_to_string(fixture.root.join("argv.log")).unwrap(), format!("{}\n", path.display()));
+    let mode = std::os::unix::fs::PermissionsExt::mode(&std::fs::metadata(&path).unwrap().permissions());
+    assert_eq!(mode & 0o200, 0);
+}
+
+#[test]
+fn touch_bumps_the_update_time_and_fixes_the_mode() {
+    let fixture = Fixture::with_scope("touch");
+    let path = fixture.add("build", "make");
+    let past = std::time::SystemTime::now() - std::time::Duration::from_secs(86400);
+    std::fs::File::open(&path).unwrap().set_modified(past).unwrap();
+    std::fs::set_permissions(&path, std::os::unix::fs::PermissionsExt::from_mode(0o644)).unwrap();
+    assert!(fixture.entry("build").unwrap().get("updated_at").is_none());
+    let output = fixture.ok(&["touch", "build", "--fix-mode"]);
+    assert!(output.contains("is executable again (mode 755)"), "{output}");
+    assert!(fixture.entry("build").unwrap()["updated_at"].as_u64().unwrap() > 0);
+    let metadata = std::fs::metadata(&path).unwrap();
+    assert!(metadata.modified().unwrap() > past + std::time::Duration::from_secs(3600));
+    assert_eq!(std::os::unix::fs::PermissionsExt::mode(&metadata.permissions()) & 0o777, 0o755);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "#!/bin/sh\nmake\n");
+}
@@ -0,0 +1,17 @@
+mod common;
+
+use common::{stdout, Fixture};
+
+#[test]
+fn export_shell_defines_a_function_per_script() {
+    let fixture = Fixture::with_scope("export-shell");
+    let build = fixture.add_with("build", &["Build it"], "echo built \"$@\"");
+    let deploy = fixture.add("deploy", "echo deployed");
+    let exported = fixture.ok(&["export-shell"]);
+    assert!(exported.contains(&format!("# Build it\nbuild() {{\n    '{}' \"$@\"\n}}\n", build.display())), "{exported}");
+    assert!(exported.contains(&format!("\ndeploy() {{\n    '{}' \"$@\"\n}}\n", deploy.display())), "{exported}");
+    let functions = fixture.root.join("functions.sh");
+    std::fs::write(&functions, &exported).unwrap();
+    let output = std::process::Command::new("sh").arg("-c").arg(format!(". {functions:?}; cd /; build x; deploy")).output().unwrap();
+    assert_eq!(stdout(&output), "built x\ndeployed\n", "{}", common::describe(&output));
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn import_aliases_creates_a_script_per_alias() {
+    let fixture = Fixture::with_scope("import-aliases");
+    let file = fixture.root.join("aliases");
+    std::fs::write(&file, "alias ll='ls -la'\n# comment\nalias gs=\"git status\"\nexport PATH=/bin\nalias up='cd ..'\n").unwrap();
+    let output = fixture.ok(&["import-aliases", file.to_str().unwrap()]);
+    assert!(output.contains("WARN: skipping line 4 which is not an alias definition: export PATH=/bin"), "{output}");
+    assert!(output.contains("INFO: imported 3 alias(es)"), "{output}");
+    for (alias, command) in [("ll", "ls -la"), ("gs", "git status"), ("up", "cd ..")] {
+        let script = std::fs::read_to_string(fixture.script_path(alias)).unwrap();
+        assert_eq!(script, format!("#!/usr/bin/env sh\n\n{command} \"$@\"\n"));
+    }
+    assert_eq!(fixture.index().as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn import_aliases_skips_an_alias_starting_with_a_dash() {
+    let fixture = Fixture::with_scope("import-aliases-dash");
+    let file = fixture.root.join("aliases");
+    std::fs::write(&file, "alias -x='ls -la'\nalias ll='ls -la'\n").unwrap();
+    let output = fixture.ok(&["import-aliases", file.to_str().unwrap()]);
+    assert!(output.contains("WARN: skipping -x because it starts with a dash"), "{output}");
+    assert!(output.contains("INFO: imported 1 alias(es)"), "{output}");
+    assert!(fixture.entry("-x").is_none());
+}
+
+#[test]
+fn failed_import_leaves_the_scope_unchanged() {
+    let fixture = Fixture::with_scope("import-aliases-rollback");
+    fixture.add("build", "make");
+    let before = fixture.index();
+    let file = fixture.root.join("aliases");
+    let aliases: String = (0..12).map(|i| format!("alias alias-number-{i}='ls -la'\n")).collect();
+    std::fs::write(&file, aliases).unwrap();
+    // saving the index fails after the scripts were moved in, as files are limited to 512 bytes which
+    // each script fits into but the index does not
+    let line = format!("trap '' XFSZ; ulimit -f 1; exec '{}' import-aliases '{}'", common::BIN, file.to_str().unwrap());
+    let output = fixture.program("sh", &["-c", &line]).output().unwrap();
+    assert!(!output.status.success());
+    assert!(common::stderr(&output).contains("unable to import the aliases, nothing was changed"), "{}", common::describe(&output));
+    assert_eq!(fixture.index(), before);
+    let scripts: Vec<_> = std::fs::read_dir(fixture.work().join(".cmd/scripts")).unwrap().map(|e| e.unwrap().file_name()).collect();
+    assert_eq!(scripts, ["build.sh"]);
+    assert!(!fixture.work().join(".cmd/import.tmp").exists());
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+use common::{stdout, Fixture};
+
+/// How many times the file is opened while `run` runs, as reported by inotify; the closes are
+/// watched too, as inotify merges repeated events which were not read yet.
+fn count_opens(path: &Path, run: impl FnOnce()) -> usize {
+    let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+    let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK) };
+    assert!(fd >= 0, "{}", std::io::Error::last_os_error());
+    assert!(unsafe { libc::inotify_add_watch(fd, path.as_ptr(), libc::IN_OPEN | libc::IN_CLOSE_NOWRITE) } >= 0);
+    run();
+    let mut opens = 0;
+    let mut buffer = [0u8; 4096];
+    loop {
+        let read = unsafe { libc::read(fd, buffer.as_mut_ptr().cast(), buffer.len()) };
+        if read <= 0 {
+            break;
+        }
+        // events of a watched file have no name, so all of them have the same size
+        for event in buffer[..read as usize].chunks_exact(std::mem::size_of::<libc::inotify_event>()) {
+            let mask = u32::from_ne_bytes(event[4..8].try_into().unwrap());
+            if mask & libc::IN_OPEN != 0 {
+                opens += 1;
+            }
+        }
+    }
+    unsafe { libc::close(fd) };
+    opens
+}
+
+#[test]
+fn list_plain_reads_each_index_once() {
+    let fixture = Fixture::with_scope("index-read-once");
+    fixture.add("build", "true");
+    fixture.ok(&["-g", "add", "tidy"]);
+    let local = fixture.work().join(".cmd/index.json");
+    let global = fixture.global().join(".cmd/index.json");
+    let mut output = String::new();
+    let global_opens = count_opens(&global, || {
+        assert_eq!(count_opens(&local, || output = fixture.ok(&["list", "--plain"])), 1);
+    });
+    assert_eq!(global_opens, 1);
+    assert_eq!(output, "tidy\nbuild\n");
+}
+
+#[test]
+fn fmt_sorts_the_index_and_check_detects_it() {
+    let fixture = Fixture::with_scope("fmt");
+    fixture.add("zip", "true");
+    fixture.add("build", "true");
+    fixture.add("deploy", "true");
+    let aliases = |fixture: &Fixture| -> Vec<String> {
+        fixture.index().as_array().unwrap().iter().map(|c| c["alias"].as_str().unwrap().to_string()).collect()
+    };
+    assert_eq!(aliases(&fixture), ["zip", "build", "deploy"]);
+    let output = fixture.run(&["fmt", "--check"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("is not formatted, run `fmt` to fix it"));
+    assert_eq!(aliases(&fixture), ["zip", "build", "deploy"]);
+    assert!(fixture.ok(&["fmt"]).contains("was formatted"));
+    assert_eq!(aliases(&fixture), ["build", "deploy", "zip"]);
+    assert_eq!(fixture.ok(&["fmt", "--check"]), "");
+    assert_eq!(fixture.ok(&["fmt"]), "");
+}
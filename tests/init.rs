@@ -0,0 +1,41 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn init_git_commits_the_scope() {
+    let fixture = Fixture::new("init-git");
+    fixture.git(&["init", "-q"]);
+    fixture.ok(&["init", "--git"]);
+    assert!(fixture.work().join(".cmd/index.json").is_file());
+    let committed = fixture.git(&["ls-files", ".cmd"]);
+    assert_eq!(committed.trim(), ".cmd/index.json");
+    assert!(fixture.git(&["status", "--porcelain", ".cmd"]).is_empty());
+}
+
+#[test]
+fn init_git_init_creates_the_repository() {
+    let fixture = Fixture::new("init-git-init");
+    fixture.ok(&["init", "--git-init"]);
+    assert!(fixture.work().join(".git").is_dir());
+    assert!(fixture.git(&["log", "--format=%s"]).contains("Initialize cmd scope"));
+}
+
+#[test]
+fn init_git_outside_of_repository_keeps_the_scope() {
+    let fixture = Fixture::new("init-git-no-repo");
+    let output = fixture.ok(&["init", "--git"]);
+    assert!(output.contains("WARN: not inside a git repository"), "{output}");
+    assert!(fixture.work().join(".cmd/index.json").is_file());
+}
+
+#[test]
+fn init_dry_run_reports_only_the_missing_pieces() {
+    let fixture = Fixture::new("init-dry-run");
+    std::fs::create_dir_all(fixture.work().join(".cmd")).unwrap();
+    let output = fixture.ok(&["init", "--dry-run"]);
+    // the scope without an index is reported by the loading of the scripts before
+    assert!(output.ends_with("exists: ./.cmd/\nwould create: ./.cmd/scripts/\nwould create: ./.cmd/index.json\n"), "{output}");
+    assert!(!fixture.work().join(".cmd/scripts").exists());
+    assert!(!fixture.work().join(".cmd/index.json").exists());
+}
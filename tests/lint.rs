@@ -0,0 +1,40 @@
+mod common;
+
+use common::{stdout, Fixture};
+
+/// Directory with a `shellcheck` which logs its arguments and flags scripts with an unquoted `$1`.
+fn shellcheck_stub(fixture: &Fixture) -> String {
+    let bin_dir = fixture.root.join("bin");
+    std::fs::create_dir_all(&bin_dir).unwrap();
+    let log = fixture.root.join("shellcheck.log");
+    let stub = format!("#!/bin/sh\necho \"$@\" >> {log:?}\nif grep -q 'echo $1' \"$3\"; then echo \"$3: SC2086\"; exit 1; fi\n");
+    std::fs::write(bin_dir.join("shellcheck"), stub).unwrap();
+    std::fs::set_permissions(bin_dir.join("shellcheck"), std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+    format!("{}:{}", bin_dir.display(), std::env::var("PATH").unwrap_or_default())
+}
+
+#[test]
+fn lint_flags_the_script_with_an_issue() {
+    let fixture = Fixture::with_scope("lint");
+    let path = shellcheck_stub(&fixture);
+    let clean = fixture.add("clean", "echo \"$1\"");
+    let sloppy = fixture.add("sloppy", "echo $1");
+    std::fs::write(&sloppy, "#!/usr/bin/env bash\necho $1\n").unwrap();
+    let output = fixture.command(&["lint"]).env("PATH", &path).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains(&format!("{}: SC2086", sloppy.display())));
+    assert!(stdout(&output).ends_with("ERR: issues found in sloppy\n"));
+    let log = std::fs::read_to_string(fixture.root.join("shellcheck.log")).unwrap();
+    assert_eq!(log, format!("--shell sh {}\n--shell bash {}\n", clean.display(), sloppy.display()));
+    let output = fixture.command(&["lint", "clean"]).env("PATH", &path).output().unwrap();
+    assert!(output.status.success(), "{}", common::describe(&output));
+}
+
+#[test]
+fn lint_without_shellcheck_prints_a_hint() {
+    let fixture = Fixture::with_scope("lint-missing");
+    fixture.add("clean", "true");
+    let output = fixture.command(&["lint"]).env("PATH", fixture.root.join("empty")).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("ERR: shellcheck is not installed"));
+}
@@ -0,0 +1,161 @@
+mod common;
+
+use common::{stdout, Fixture};
+
+#[test]
+fn long_description_is_truncated_to_the_width_unless_full() {
+    let fixture = Fixture::with_scope("list-width");
+    let description = "Build the whole project including the documentation and the examples";
+    fixture.add_with("build", &[description], "make");
+    let narrow = fixture.ok(&["list", "--width", "40"]);
+    let line = narrow.lines().find(|l| l.starts_with("build")).unwrap();
+    assert_eq!(line.chars().count(), 40, "{line}");
+    assert!(line.ends_with('…') && line.contains("local"), "{line}");
+    let full = fixture.ok(&["list", "--full"]);
+    assert!(full.contains(description), "{full}");
+    // output to a pipe has no width to fit into
+    assert!(fixture.ok(&["list"]).contains(description));
+}
+
+#[test]
+fn recent_lists_the_last_run_script_first() {
+    let fixture = Fixture::with_scope("list-recent");
+    fixture.add("a", "true");
+    fixture.add("b", "true");
+    fixture.ok(&["b"]);
+    // the runs are recorded in seconds
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    fixture.ok(&["a"]);
+    let recent = fixture.ok(&["list", "--recent"]);
+    let aliases: Vec<&str> = recent.lines().filter_map(|l| l.split_whitespace().next()).collect();
+    assert_eq!(aliases, ["a", "b"], "{recent}");
+    assert_eq!(fixture.ok(&["list", "--recent", "1", "--plain"]), "a\n");
+}
+
+#[test]
+fn grouped_scripts_are_listed_in_sections() {
+    let fixture = Fixture::with_scope("list-group");
+    fixture.add("loose", "true");
+    fixture.add_with("deploy", &["--group", "ship"], "true");
+    fixture.add_with("build", &["--group", "make"], "true");
+    fixture.add_with("release", &["--group", "ship"], "true");
+    let list = fixture.ok(&["list"]);
+    let words: Vec<&str> = list.lines().map(|l| l.split_whitespace().next().unwrap_or("")).collect();
+    assert_eq!(words, ["ship:", "deploy", "release", "", "make:", "build", "", "misc:", "loose"], "{list}");
+    assert_eq!(fixture.ok(&["list", "--plain", "--group", "ship"]), "deploy\nrelease\n");
+    assert_eq!(fixture.ok(&["list", "--plain", "--group", "misc"]), "loose\n");
+}
+
+#[test]
+fn long_listing_shows_the_line_count_and_sorts_by_size() {
+    let fixture = Fixture::with_scope("list-long");
+    let small = fixture.add("small", "true");
+    let large = fixture.add("large", "echo one\necho two\necho three");
+    fixture.add("gone", "true");
+    std::fs::remove_file(fixture.script_path("gone")).unwrap();
+    let list = fixture.ok(&["list", "--long", "--sort", "size"]);
+    let rows: Vec<Vec<&str>> = list.lines().map(|l| l.split_whitespace().collect()).collect();
+    let size = |path| std::fs::metadata(path).unwrap().len().to_string();
+    // alias, scope, size, date, time, lines
+    assert_eq!(rows[0][..3], ["large", "local", &size(&large)], "{list}");
+    assert_eq!(rows[0][5], "4", "{list}");
+    assert_eq!(rows[1][..3], ["small", "local", &size(&small)], "{list}");
+    assert_eq!(rows[1][5], "2", "{list}");
+    assert_eq!(rows[2], ["gone", "local", "—", "—", "—"], "{list}");
+}
+
+#[test]
+fn plain_list_is_filtered_by_the_scope_flags() {
+    let fixture = Fixture::with_scope("list-scope");
+    fixture.add("local-one", "true");
+    fixture.ok(&["-g", "add", "global-one"]);
+    // the way the dynamic completion passes the scope flags through
+    assert_eq!(fixture.ok(&["-g", "--list", "--plain"]), "global-one\n");
+    assert_eq!(fixture.ok(&["-l", "--list", "--plain"]), "local-one\n");
+    assert_eq!(fixture.ok(&["--list", "--plain"]), "global-one\nlocal-one\n");
+}
+
+#[test]
+fn usage_listing_shows_the_declared_usage() {
+    let fixture = Fixture::with_scope("list-usage");
+    fixture.add_with("deploy", &["--usage", "<env> [--force]"], "true");
+    fixture.add("build", "true");
+    assert_eq!(fixture.ok(&["list", "--usage"]), "deploy  <env> [--force]\nbuild\n");
+}
+
+#[test]
+fn runs_are_counted_and_popular_lists_the_most_run_first() {
+    let fixture = Fixture::with_scope("list-popular");
+    fixture.add("once", "true");
+    fixture.add("twice", "true");
+    assert!(fixture.runs("twice").is_none());
+    fixture.ok(&["once"]);
+    fixture.ok(&["twice"]);
+    fixture.ok(&["twice"]);
+    let runs = fixture.runs("twice").unwrap();
+    assert_eq!(runs["count"], 2);
+    assert!(runs["last"].as_u64().unwrap() > 0);
+    assert_eq!(fixture.runs("once").unwrap()["count"], 1);
+    assert!(fixture.entry("twice").unwrap().get("run_count").is_none());
+    assert_eq!(fixture.ok(&["list", "--popular", "--plain"]), "twice\nonce\n");
+}
+
+#[test]
+fn parallel_runs_are_all_counted() {
+    let fixture = Fixture::with_scope("list-popular-parallel");
+    fixture.add("build", "true");
+    let children: Vec<_> = (0..30).map(|_| fixture.command(&["build"]).spawn().unwrap()).collect();
+    for mut child in children {
+        assert!(child.wait().unwrap().success());
+    }
+    assert_eq!(fixture.runs("build").unwrap()["count"], 30);
+    let leftovers: Vec<_> = std::fs::read_dir(fixture.runs_path().parent().unwrap()).unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.ends_with(".tmp"))
+        .collect();
+    assert!(leftovers.is_empty(), "{leftovers:?}");
+}
+
+#[test]
+fn runs_recorded_in_the_index_are_still_read() {
+    let fixture = Fixture::with_scope("list-popular-legacy");
+    fixture.add("old", "true");
+    fixture.add("new", "true");
+    let mut index = fixture.index();
+    index[0]["run_count"] = 5.into();
+    index[0]["last_run"] = 1.into();
+    std::fs::write(fixture.work().join(".cmd/index.json"), index.to_string()).unwrap();
+    fixture.ok(&["new"]);
+    assert_eq!(fixture.ok(&["list", "--popular", "--plain"]), "old\nnew\n");
+    fixture.ok(&["old"]);
+    assert_eq!(fixture.runs("old").unwrap()["count"], 6);
+}
+
+#[test]
+fn run_without_a_writable_state_only_warns() {
+    let fixture = Fixture::with_scope("list-popular-readonly");
+    fixture.add("build", "echo built");
+    // a file in place of the state directory makes the write fail even for root
+    let dir = fixture.runs_path().parent().unwrap().to_path_buf();
+    std::fs::create_dir_all(dir.parent().unwrap()).unwrap();
+    std::fs::write(&dir, "").unwrap();
+    let output = fixture.run(&["build"]);
+    assert!(output.status.success(), "{}", common::describe(&output));
+    assert!(common::stderr(&output).starts_with("WARN: unable to record the run of build"), "{}", common::describe(&output));
+    assert_eq!(stdout(&output), "built\n");
+    assert!(fixture.runs("build").is_none());
+}
+
+#[test]
+fn columns_are_shown_in_the_requested_order() {
+    let fixture = Fixture::with_scope("list-columns");
+    fixture.add_with("build", &["Build it", "--tag", "ci,fast"], "make");
+    let list = fixture.ok(&["list", "--columns", "path,alias,tags"]);
+    let row: Vec<&str> = list.lines().next().unwrap().split_whitespace().collect();
+    let path = fixture.work().join(".cmd/scripts/build.sh");
+    assert_eq!(row, [path.to_str().unwrap(), "build", "ci,fast"], "{list}");
+    assert!(!list.contains("Build it") && !list.contains("local"), "{list}");
+    let output = fixture.run(&["list", "--columns", "alias,size"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(common::stderr(&output).contains("invalid value 'size' for '--columns <NAMES>'"), "{}", common::stderr(&output));
+}
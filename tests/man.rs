@@ -0,0 +1,16 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn man_page_lists_the_builtins_and_the_scripts() {
+    let fixture = Fixture::with_scope("man");
+    fixture.add_with("build", &["Build the project"], "make");
+    let page = fixture.ok(&["man", "--scripts"]);
+    assert!(page.contains(".TH shell-scriptman 1"), "{page}");
+    for builtin in ["init", "add", "list", "remove", "completions"] {
+        assert!(page.contains(&format!("shell\\-scriptman\\-{builtin}(1)")), "{builtin} is missing");
+    }
+    assert!(page.contains(".SH SCRIPTS\n.TP\n\\fBbuild\\fR (local)\nBuild the project"), "{page}");
+    assert!(!fixture.ok(&["man"]).contains(".SH SCRIPTS"));
+}
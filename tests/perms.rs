@@ -0,0 +1,38 @@
+mod common;
+
+use std::os::unix::fs::PermissionsExt;
+
+use common::{stdout, Fixture};
+
+#[test]
+fn world_writable_script_is_flagged_and_fixed() {
+    let fixture = Fixture::with_scope("check-perms");
+    let safe = fixture.add("safe", "true");
+    std::fs::set_permissions(&safe, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let open = fixture.add("open", "true");
+    std::fs::set_permissions(&open, std::fs::Permissions::from_mode(0o777)).unwrap();
+    let output = fixture.run(&["check-perms"]);
+    assert_eq!(output.status.code(), Some(1));
+    let report = stdout(&output);
+    assert!(report.contains(&format!("WARN: {} is writable by group or others (mode 777)", open.display())), "{report}");
+    assert!(!report.contains(&safe.display().to_string()), "{report}");
+    fixture.ok(&["check-perms", "--fix"]);
+    assert_eq!(std::fs::metadata(&open).unwrap().permissions().mode() & 0o777, 0o755);
+    assert_eq!(fixture.ok(&["check-perms"]), "");
+}
+
+#[test]
+fn chmod_sets_the_mode_of_a_new_and_an_existing_script() {
+    let fixture = Fixture::with_scope("chmod");
+    let mode = |path: &std::path::Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o7777;
+    fixture.ok(&["add", "private", "--chmod", "0700"]);
+    assert_eq!(mode(&fixture.script_path("private")), 0o700);
+    fixture.ok(&["add", "shared"]);
+    assert_eq!(mode(&fixture.script_path("shared")), 0o775);
+    fixture.ok(&["chmod", "shared", "750"]);
+    assert_eq!(mode(&fixture.script_path("shared")), 0o750);
+    let output = fixture.run(&["chmod", "shared", "rwx"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(common::stderr(&output).contains("expected an octal mode such as 0700 or 755"));
+    assert_eq!(mode(&fixture.script_path("shared")), 0o750);
+}
@@ -0,0 +1,19 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn recorded_session_becomes_a_script() {
+    let fixture = Fixture::with_scope("record");
+    // stands in for an interactive shell, it leaves the typed lines in its history
+    let shell = fixture.root.join("sh");
+    std::fs::write(&shell, "#!/bin/sh\nprintf 'echo one\\n: 1700000000:0;echo two\\nexit\\n' > \"$HISTFILE\"\n").unwrap();
+    std::fs::set_permissions(&shell, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+    let output = fixture.command(&["record", "greet", "Say hello"]).env("SHELL", &shell).output().unwrap();
+    assert!(output.status.success(), "{}", common::describe(&output));
+    assert!(common::stdout(&output).contains("INFO: recorded 2 command(s) into ./.cmd/scripts/greet.sh"));
+    let script = std::fs::read_to_string(fixture.script_path("greet")).unwrap();
+    assert_eq!(script, "#!/usr/bin/env sh\n\necho one\necho two\n");
+    assert_eq!(fixture.entry("greet").unwrap()["description"], "Say hello");
+    assert_eq!(fixture.ok(&["greet"]), "one\ntwo\n");
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::{stdout, Fixture};
+
+#[test]
+fn swap_exchanges_the_aliases() {
+    let fixture = Fixture::with_scope("swap");
+    let a = fixture.add("a", "echo A");
+    let b = fixture.add("b", "echo B");
+    fixture.ok(&["swap", "a", "b"]);
+    assert_eq!(fixture.script_path("a"), b);
+    assert_eq!(fixture.script_path("b"), a);
+    assert_eq!(fixture.ok(&["a"]), "B\n");
+    assert_eq!(fixture.ok(&["b"]), "A\n");
+}
+
+#[test]
+fn swap_with_files_exchanges_also_the_files() {
+    let fixture = Fixture::with_scope("swap-files");
+    let a = fixture.add("a", "echo A");
+    let b = fixture.add("b", "echo B");
+    fixture.ok(&["swap", "a", "b", "--files"]);
+    assert_eq!(fixture.script_path("a"), a);
+    assert_eq!(fixture.script_path("b"), b);
+    assert!(std::fs::read_to_string(&a).unwrap().contains("echo B"));
+    assert!(std::fs::read_to_string(&b).unwrap().contains("echo A"));
+    assert_eq!(fixture.ok(&["a"]), "B\n");
+}
+
+#[test]
+fn swap_across_scopes_is_refused() {
+    let fixture = Fixture::with_scope("swap-scopes");
+    fixture.add("a", "echo A");
+    fixture.ok(&["-g", "add", "b"]);
+    let before = fixture.index();
+    let output = fixture.run(&["swap", "a", "b"]);
+    assert!(!output.status.success());
+    assert_eq!(fixture.index(), before);
+    assert!(stdout(&fixture.run(&["a"])).contains('A'));
+}
+
+#[test]
+fn rename_updates_the_dependencies_on_the_script() {
+    let fixture = Fixture::with_scope("rename-depends");
+    fixture.add("build", "echo build");
+    fixture.add_with("deploy", &["--depends", "build"], "echo deploy");
+    fixture.ok(&["rename", "build", "compile"]);
+    assert_eq!(fixture.entry("deploy").unwrap()["depends"], serde_json::json!(["compile"]));
+    assert_eq!(fixture.ok(&["deploy"]), "build\ndeploy\n");
+}
+
+#[test]
+fn swap_updates_the_dependencies_on_the_scripts() {
+    let fixture = Fixture::with_scope("swap-depends");
+    fixture.add("build", "echo build");
+    fixture.add_with("deploy", &["--depends", "build"], "echo deploy");
+    fixture.ok(&["swap", "build", "deploy", "--files"]);
+    assert_eq!(fixture.entry("build").unwrap()["depends"], serde_json::json!(["deploy"]));
+    assert!(fixture.entry("deploy").unwrap().get("depends").is_none());
+    assert_eq!(fixture.ok(&["build"]), "build\ndeploy\n");
+}
+
+#[test]
+fn remove_of_a_dependency_requires_force() {
+    let fixture = Fixture::with_scope("remove-depends");
+    fixture.add("build", "echo build");
+    fixture.add_with("deploy", &["--depends", "build"], "echo deploy");
+    let output = fixture.run(&["remove", "build"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("ERR: build is a dependency of deploy, use --force to remove it anyway"));
+    assert!(fixture.entry("build").is_some());
+    let output = fixture.ok(&["remove", "build", "--force"]);
+    assert!(output.contains("WARN"), "{output}");
+    assert!(fixture.entry("build").is_none());
+}
+
+#[test]
+fn remove_dry_run_only_reports_the_deletions() {
+    let fixture = Fixture::with_scope("remove-dry-run");
+    let path = fixture.add("foo", "true");
+    let before = fixture.index();
+    let output = fixture.ok(&["remove", "foo", "--purge", "--dry-run"]);
+    assert_eq!(output, format!("would remove: foo from {}\nwould delete: {}\n", fixture.work().join(".cmd/index.json").display(), path.display()));
+    assert_eq!(fixture.index(), before);
+    assert!(path.is_file());
+}
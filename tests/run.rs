@@ -0,0 +1,187 @@
+mod common;
+
+use common::{stdout, Fixture};
+
+#[test]
+fn capture_saves_both_streams_and_keeps_the_exit_code() {
+    let fixture = Fixture::with_scope("capture");
+    fixture.add("noisy", "echo to-out\necho to-err >&2\nexit 3");
+    let dir = fixture.root.join("logs");
+    let output = fixture.run(&["--capture", dir.to_str().unwrap(), "noisy"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(std::fs::read_to_string(dir.join("noisy.out")).unwrap(), "to-out\n");
+    assert_eq!(std::fs::read_to_string(dir.join("noisy.err")).unwrap(), "to-err\n");
+    assert!(stdout(&output).contains("to-out"));
+    let output = fixture.run(&["--capture", dir.to_str().unwrap(), "--quiet", "noisy"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(!stdout(&output).contains("to-out"));
+    assert!(!common::stderr(&output).contains("to-err"));
+    assert_eq!(std::fs::read_to_string(dir.join("noisy.out")).unwrap(), "to-out\n");
+}
+
+#[test]
+fn via_shell_runs_a_script_which_is_not_executable() {
+    let fixture = Fixture::with_scope("via-shell");
+    let path = fixture.add("plain", "echo ran \"$1\"");
+    std::fs::set_permissions(&path, std::os::unix::fs::PermissionsExt::from_mode(0o644)).unwrap();
+    assert!(!fixture.run(&["plain", "x"]).status.success());
+    assert_eq!(fixture.ok(&["--via-shell", "plain", "x"]), "ran x\n");
+}
+
+#[test]
+fn dependency_runs_first_and_its_failure_aborts() {
+    let fixture = Fixture::with_scope("depends");
+    let build = fixture.add("build", "echo build");
+    fixture.add_with("deploy", &["--depends", "build"], "echo deploy");
+    assert_eq!(fixture.ok(&["deploy"]), "build\ndeploy\n");
+    std::fs::write(&build, "#!/bin/sh\necho broken\nexit 2\n").unwrap();
+    let output = fixture.run(&["deploy"]);
+    assert_eq!(output.status.code(), Some(2));
+    let report = stdout(&output);
+    assert!(report.starts_with("broken\n") && report.ends_with("ERR: dependency build failed, deploy is not run\n"), "{report}");
+}
+
+#[test]
+fn dependency_cycle_is_refused() {
+    let fixture = Fixture::with_scope("depends-cycle");
+    fixture.add("build", "true");
+    fixture.add_with("deploy", &["--depends", "build"], "true");
+    let before = fixture.index();
+    let output = fixture.run(&["depends", "build", "deploy"]);
+    assert!(!output.status.success());
+    assert!(common::stderr(&output).contains("dependency cycle"), "{}", common::describe(&output));
+    assert_eq!(fixture.index(), before);
+}
+
+#[test]
+fn env_file_sets_variables_which_env_overrides() {
+    let fixture = Fixture::with_scope("env-file");
+    fixture.add("greet", "echo \"$GREETING, $NAME$SUFFIX\"");
+    let env_file = fixture.work().join(".env");
+    std::fs::write(&env_file, "# greeting of the tests\nGREETING=\"Hello there\"\nexport NAME='world'\nSUFFIX=!\n").unwrap();
+    let output = fixture.command(&["--env-file", ".env", "greet"]).env("NAME", "inherited").output().unwrap();
+    assert_eq!(stdout(&output), "Hello there, world!\n", "{}", common::describe(&output));
+    assert_eq!(fixture.ok(&["--env-file", ".env", "--env", "NAME=you", "greet"]), "Hello there, you!\n");
+}
+
+#[test]
+fn run_all_runs_only_the_tagged_scripts() {
+    let fixture = Fixture::with_scope("run-all");
+    fixture.add_with("test", &["--tag", "ci"], "echo ran test");
+    fixture.add_with("lint-all", &["--tag", "ci,other"], "echo ran lint-all");
+    fixture.add_with("deploy", &["--tag", "other"], "echo ran deploy");
+    let output = fixture.ok(&["run-all", "ci"]);
+    assert_eq!(output, "==> lint-all\nran lint-all\n==> test\nran test\nINFO: 2 succeeded, 0 failed, 0 not run\n");
+}
+
+#[test]
+fn run_all_stops_at_a_failure_unless_keep_going() {
+    let fixture = Fixture::with_scope("run-all-failure");
+    fixture.add_with("a", &["--tag", "ci"], "exit 3");
+    fixture.add_with("b", &["--tag", "ci"], "echo ran b");
+    let output = fixture.run(&["run-all", "ci"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(!stdout(&output).contains("ran b"));
+    assert!(stdout(&output).contains("INFO: 0 succeeded, 1 failed, 1 not run\nERR: a exited with code 3\n"));
+    let output = fixture.run(&["run-all", "ci", "--keep-going"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(stdout(&output).contains("ran b\nINFO: 1 succeeded, 1 failed, 0 not run\n"));
+}
+
+#[test]
+fn timeout_kills_the_script_with_exit_code_124() {
+    let fixture = Fixture::with_scope("timeout");
+    fixture.add("hang", "echo started\nsleep 30\necho finished");
+    let start = std::time::Instant::now();
+    let output = fixture.run(&["--timeout", "1", "hang"]);
+    assert!(start.elapsed() < std::time::Duration::from_secs(10));
+    assert_eq!(output.status.code(), Some(124));
+    assert_eq!(stdout(&output), "started\nERR: command 'hang' timed out after 1s\n");
+    fixture.add("quick", "exit 4");
+    assert_eq!(fixture.run(&["--timeout", "5", "quick"]).status.code(), Some(4));
+}
+
+#[test]
+fn stdin_args_separated_by_nul_keep_spaces_and_newlines() {
+    let fixture = Fixture::with_scope("stdin-args-null");
+    fixture.add("show", "printf '[%s]\\n' \"$@\"");
+    let run = |args: &[&str], input: &[u8]| {
+        let mut child = fixture.command(args).stdin(std::process::Stdio::piped()).stdout(std::process::Stdio::piped()).spawn().unwrap();
+        std::io::Write::write_all(&mut child.stdin.take().unwrap(), input).unwrap();
+        stdout(&child.wait_with_output().unwrap())
+    };
+    assert_eq!(run(&["--stdin-args", "-0", "show", "first"], b"a file.txt\0two\nlines\0"), "[first]\n[a file.txt]\n[two\nlines]\n");
+    assert_eq!(run(&["--stdin-args", "show"], b"a file.txt\nb\n"), "[a file.txt]\n[b]\n");
+}
+
+#[test]
+fn capture_json_prints_the_result_of_the_run() {
+    let fixture = Fixture::with_scope("capture-json");
+    fixture.add("noisy", "echo to-out\necho to-err >&2\nexit 5");
+    let output = fixture.run(&["--capture-json", "noisy"]);
+    assert_eq!(output.status.code(), Some(5));
+    let result: serde_json::Value = serde_json::from_str(stdout(&output).trim_end()).unwrap();
+    assert_eq!(result["alias"], "noisy");
+    assert_eq!(result["exit_code"], 5);
+    assert_eq!(result["stdout"], "to-out\n");
+    assert_eq!(result["stderr"], "to-err\n");
+    assert!(result["duration_ms"].is_u64());
+    assert_eq!(result["dependencies"], serde_json::json!([]));
+    assert_eq!(stdout(&output).lines().count(), 1);
+}
+
+#[test]
+fn capture_json_nests_the_dependencies_into_one_object() {
+    let fixture = Fixture::with_scope("capture-json-depends");
+    let build = fixture.add("build", "echo built");
+    fixture.add_with("deploy", &["--depends", "build"], "echo deployed");
+    let output = fixture.run(&["--capture-json", "deploy"]);
+    assert!(output.status.success(), "{}", common::describe(&output));
+    let result: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(result["alias"], "deploy");
+    assert_eq!(result["stdout"], "deployed\n");
+    assert_eq!(result["dependencies"][0]["alias"], "build");
+    assert_eq!(result["dependencies"][0]["stdout"], "built\n");
+    assert_eq!(result["dependencies"].as_array().unwrap().len(), 1);
+    std::fs::write(&build, "#!/bin/sh\nexit 2\n").unwrap();
+    let output = fixture.run(&["--capture-json", "deploy"]);
+    assert_eq!(output.status.code(), Some(2));
+    let result: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(result["alias"], "deploy");
+    assert_eq!(result["exit_code"], 2);
+    assert_eq!(result["not_run"], true);
+    assert_eq!(result["dependencies"][0]["exit_code"], 2);
+    assert!(common::stderr(&output).contains("ERR: dependency build failed, deploy is not run"), "{}", common::describe(&output));
+}
+
+#[test]
+fn default_args_are_used_only_without_arguments() {
+    let fixture = Fixture::with_scope("default-args");
+    fixture.add_with("build", &["--default-args", "--fast 'two words'", "--tag", "ci"], "printf '[%s]' \"$@\"; echo");
+    fixture.add_with("deploy", &["--depends", "build"], "echo deploy \"$@\"");
+    assert_eq!(fixture.ok(&["build"]), "[--fast][two words]\n");
+    assert_eq!(fixture.ok(&["build", "slow"]), "[slow]\n");
+    assert_eq!(fixture.ok(&["deploy", "prod"]), "[--fast][two words]\ndeploy prod\n");
+    assert!(fixture.ok(&["run-all", "ci"]).contains("[--fast][two words]\n"));
+    let functions = fixture.root.join("functions.sh");
+    std::fs::write(&functions, fixture.ok(&["export-shell"])).unwrap();
+    let output = std::process::Command::new("sh").arg("-c").arg(format!(". {functions:?}; build; build x; deploy")).output().unwrap();
+    assert_eq!(stdout(&output), "[--fast][two words]\n[x]\n[--fast][two words]\ndeploy\n", "{}", common::describe(&output));
+}
+
+#[test]
+fn run_leaves_the_index_untouched() {
+    let fixture = Fixture::with_scope("run-index-untouched");
+    fixture.add("build", "true");
+    let rel_path = fixture.entry("build").unwrap()["rel_path"].as_str().unwrap().to_string();
+    std::fs::remove_file(fixture.work().join(".cmd/index.json")).unwrap();
+    let yaml = fixture.work().join(".cmd/index.yaml");
+    let index = format!("# kept in the repository\n- alias: build\n  rel_path: {rel_path}\n  description: ''\n");
+    std::fs::write(&yaml, &index).unwrap();
+    fixture.ok(&["build"]);
+    assert_eq!(std::fs::read_to_string(&yaml).unwrap(), index);
+    // a change of the index while a script runs is not reverted by the end of the run
+    std::fs::write(fixture.work().join(&rel_path), format!("#!/bin/sh\n'{}' describe build 'changed meanwhile'\n", common::BIN)).unwrap();
+    fixture.ok(&["build"]);
+    assert!(std::fs::read_to_string(&yaml).unwrap().contains("changed meanwhile"));
+}
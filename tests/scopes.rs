@@ -0,0 +1,85 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn symlinked_cmd_directory_is_used_transparently() {
+    let fixture = Fixture::new("symlinked-cmd");
+    let shared = fixture.root.join("shared");
+    std::fs::create_dir_all(&shared).unwrap();
+    fixture.command_in(&shared, &["init"]).output().unwrap();
+    std::os::unix::fs::symlink(shared.join(".cmd"), fixture.work().join(".cmd")).unwrap();
+    fixture.add("build", "echo built");
+    assert!(shared.join(".cmd/scripts/build.sh").is_file());
+    assert!(fixture.work().join(".cmd").symlink_metadata().unwrap().file_type().is_symlink());
+    assert_eq!(fixture.ok(&["build"]), "built\n");
+    assert_eq!(common::read_index(&shared), fixture.index());
+}
+
+#[test]
+fn dangling_cmd_symlink_is_reported() {
+    let fixture = Fixture::new("dangling-cmd");
+    let target = fixture.root.join("nowhere");
+    std::os::unix::fs::symlink(&target, fixture.work().join(".cmd")).unwrap();
+    let output = fixture.command(&["list"]).env("RUST_BACKTRACE", "0").output().unwrap();
+    let expected = format!("ERR: '{}' is a symlink to '{}' which does not exist", fixture.work().join(".cmd").display(), target.display());
+    assert!(common::stdout(&output).contains(&expected), "{}", common::describe(&output));
+    assert!(!common::stderr(&output).contains("panicked"));
+}
+
+#[test]
+fn scope_info_reports_the_local_scope_of_an_ancestor() {
+    let fixture = Fixture::with_scope("scope-info");
+    let nested = fixture.work().join("src/deeper");
+    std::fs::create_dir_all(&nested).unwrap();
+    let output = fixture.command_in(&nested, &["scope-info"]).output().unwrap();
+    let info = common::stdout(&output);
+    assert!(info.contains(&format!("current directory: {}\n", nested.display())), "{info}");
+    assert!(info.contains(&format!("local scope: {} (nearest ancestor with .cmd, initialized)\n", fixture.work().display())), "{info}");
+    assert!(info.contains(&format!("global scope: {} (", fixture.global().display())), "{info}");
+}
+
+#[test]
+fn where_prints_the_executable() {
+    let fixture = Fixture::new("where");
+    let output = fixture.ok(&["where"]);
+    let exe = std::fs::canonicalize(common::BIN).unwrap();
+    assert!(output.starts_with(&format!("executable: {}\n", exe.display())), "{output}");
+    assert!(output.contains(&format!("global scope: {}\n", fixture.global().display())), "{output}");
+    assert!(output.contains(&format!("config: {}\n", fixture.config_dir().join("config.toml").display())), "{output}");
+}
+
+#[test]
+fn moved_scope_runs_from_its_new_place() {
+    let fixture = Fixture::with_scope("rename-scope-path");
+    fixture.add("build", "echo built");
+    let moved = fixture.root.join("moved");
+    let output = fixture.ok(&["rename-scope-path", "../moved"]);
+    assert!(output.contains(&format!("INFO: moved {} to {}", fixture.work().join(".cmd").display(), moved.join(".cmd").display())), "{output}");
+    assert!(!fixture.work().join(".cmd").exists());
+    let output = fixture.command_in(&moved, &["build"]).output().unwrap();
+    assert_eq!(common::stdout(&output), "built\n", "{}", common::describe(&output));
+}
+
+#[test]
+fn scope_is_not_moved_over_an_existing_one() {
+    let fixture = Fixture::with_scope("rename-scope-path-taken");
+    fixture.add("build", "true");
+    let taken = fixture.root.join("taken");
+    std::fs::create_dir_all(taken.join(".cmd")).unwrap();
+    let output = fixture.run(&["rename-scope-path", "../taken"]);
+    assert!(!output.status.success());
+    assert!(fixture.script_path("build").is_file());
+    assert_eq!(std::fs::read_dir(taken.join(".cmd")).unwrap().count(), 0);
+}
+
+#[test]
+fn moved_global_scope_is_remembered_in_the_config() {
+    let fixture = Fixture::new("rename-scope-path-global");
+    fixture.ok(&["-g", "add", "tidy"]);
+    let moved = fixture.root.join("new-global");
+    std::fs::create_dir_all(&moved).unwrap();
+    fixture.ok(&["-g", "rename-scope-path", moved.to_str().unwrap()]);
+    assert_eq!(fixture.ok(&["config", "global-scope"]), format!("{}\n", moved.display()));
+    assert_eq!(fixture.ok(&["list", "--plain"]), "tidy\n");
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use common::{stdout, Fixture};
+
+#[test]
+fn validate_all_reports_each_nested_scope() {
+    let fixture = Fixture::with_scope("validate-all");
+    fixture.git(&["init", "-q"]);
+    fixture.add("build", "true");
+    fixture.ok(&["-g", "add", "tidy"]);
+    let valid = fixture.work().join("tools");
+    std::fs::create_dir_all(&valid).unwrap();
+    fixture.command_in(&valid, &["init"]).output().unwrap();
+    let malformed = fixture.work().join("services/api");
+    std::fs::create_dir_all(malformed.join(".cmd/scripts")).unwrap();
+    let index = r#"[
+        {"alias": "x", "rel_path": "../../../etc/passwd", "description": ""},
+        {"alias": "x", "rel_path": "./.cmd/scripts/x.sh", "description": ""}
+    ]"#;
+    std::fs::write(malformed.join(".cmd/index.json"), index).unwrap();
+    let output = fixture.run(&["validate-all"]);
+    assert_eq!(output.status.code(), Some(1));
+    let report = stdout(&output);
+    assert!(report.contains(&format!("global scope {}: ok\n", fixture.global().display())), "{report}");
+    assert!(report.contains(&format!("local scope {}: ok\n", fixture.work().display())), "{report}");
+    assert!(report.contains(&format!("local scope {}: ok\n", valid.display())), "{report}");
+    assert!(report.contains(&format!("local scope {}: 2 problem(s)\n", malformed.display())), "{report}");
+    assert!(report.contains("  ERR: rel_path of 'x' leads outside of the scope: ../../../etc/passwd\n"), "{report}");
+    assert!(report.contains("  ERR: alias 'x' is used more than once\n"), "{report}");
+    assert!(report.contains("ERR: 1 of 4 scopes are invalid"), "{report}");
+}
+
+#[test]
+fn self_check_reports_a_bad_alias_without_crashing() {
+    let fixture = Fixture::with_scope("self-check");
+    fixture.add("build", "true");
+    let index = r#"[
+        {"alias": "build", "rel_path": "./.cmd/scripts/build.sh", "description": ""},
+        {"alias": "-bad", "rel_path": "./.cmd/scripts/build.sh", "description": ""}
+    ]"#;
+    std::fs::write(fixture.work().join(".cmd/index.json"), index).unwrap();
+    let output = fixture.run(&["self-check"]);
+    assert_eq!(output.status.code(), Some(1));
+    let report = stdout(&output);
+    assert!(report.contains("ERR: alias '-bad' cannot be used as a command"), "{report}");
+    assert!(report.ends_with("ERR: the self-check failed in 1 place(s)\n"), "{report}");
+    assert!(!common::stderr(&output).contains("panicked"), "{}", common::stderr(&output));
+}
+
+#[test]
+fn validate_all_skips_dependencies_and_other_repositories() {
+    let fixture = Fixture::with_scope("validate-all-bounds");
+    fixture.git(&["init", "-q"]);
+    for dir in ["node_modules/pkg", "target/debug", "vendor/other-repo/tools", "a/b/c/d/e/f/g"] {
+        let path = fixture.work().join(dir);
+        std::fs::create_dir_all(path.join(".cmd")).unwrap();
+        std::fs::write(path.join(".cmd/index.json"), "{broken").unwrap();
+    }
+    std::fs::create_dir_all(fixture.work().join("vendor/other-repo/.git")).unwrap();
+    let output = fixture.run(&["validate-all"]);
+    assert!(output.status.success(), "{}", common::describe(&output));
+    assert!(!stdout(&output).contains("broken") && !stdout(&output).contains("problem"), "{}", stdout(&output));
+}
+
+#[test]
+fn alias_with_a_slash_passes_the_validation() {
+    let fixture = Fixture::with_scope("validate-slash");
+    fixture.add("db/migrate", "true");
+    assert!(fixture.ok(&["validate-all"]).contains(&format!("local scope {}: ok\n", fixture.work().display())));
+    fixture.ok(&["self-check"]);
+}